@@ -1,5 +1,5 @@
 use rand::distributions::{Distribution, Uniform};
-use std::{path::Path};
+use std::path::Path;
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use path_no_alloc::with_paths;
@@ -7,8 +7,8 @@ use path_no_alloc::with_paths;
 pub fn array_from_idx<const N: usize, T>(f: impl FnMut(usize) -> T) -> [T; N] {
     let mut indices = [0; N];
 
-    for i in 0..N {
-        indices[i] = i;
+    for (i, index) in indices.iter_mut().enumerate() {
+        *index = i;
     }
 
     indices.map(f)
@@ -57,8 +57,7 @@ pub fn join_random(c: &mut Criterion) {
     for mean_len in (1..12).map(|i| i * 10) {
         let length_dist = Uniform::from(0..mean_len);
 
-        let mut paths: Vec<String> = vec![];
-        paths.reserve(SAMPLE_SIZE);
+        let mut paths: Vec<String> = Vec::with_capacity(SAMPLE_SIZE);
 
         for _ in 0..SAMPLE_SIZE {
             let len = length_dist.sample(&mut rng);
@@ -85,7 +84,8 @@ pub fn join_random(c: &mut Criterion) {
             })
         });
 
-        i1 = 0; i2 = 0;
+        i1 = 0;
+        i2 = 0;
         group.bench_function(BenchmarkId::new("Path.join", mean_len), |b| {
             b.iter(|| {
                 let p1 = &paths[i1];
@@ -110,8 +110,7 @@ pub fn exists_random(c: &mut Criterion) {
     for mean_len in (1..12).map(|i| i * 10) {
         let length_dist = Uniform::from(0..mean_len);
 
-        let mut paths: Vec<String> = vec![];
-        paths.reserve(SAMPLE_SIZE);
+        let mut paths: Vec<String> = Vec::with_capacity(SAMPLE_SIZE);
 
         for _ in 0..SAMPLE_SIZE {
             let len = length_dist.sample(&mut rng);
@@ -138,7 +137,8 @@ pub fn exists_random(c: &mut Criterion) {
             })
         });
 
-        i1 = 0; i2 = 0;
+        i1 = 0;
+        i2 = 0;
         group.bench_function(BenchmarkId::new("Path.join", mean_len), |b| {
             b.iter(|| {
                 let p1 = &paths[i1];
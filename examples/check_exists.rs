@@ -1,4 +1,4 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 use path_no_alloc::with_paths;
 
@@ -8,7 +8,7 @@ where
     R2: AsRef<Path>,
 {
     for path in paths {
-        with_paths!{
+        with_paths! {
             path = root / path
         };
 
@@ -21,8 +21,5 @@ where
 }
 
 fn main() {
-    check_exists(
-        "src",
-        ["lib.rs", "tests.rs", "some-other-file.txt"]
-    );
+    check_exists("src", ["lib.rs", "tests.rs", "some-other-file.txt"]);
 }
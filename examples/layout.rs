@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use path_no_alloc::PathLayout;
+
+#[derive(PathLayout)]
+struct Cache {
+    #[layout(artifact = "{hash}/artifact.bin")]
+    dir: PathBuf,
+}
+
+#[derive(PathLayout)]
+struct Layout {
+    #[layout(
+        build_log = "projects/{project}/builds/{id}.log",
+        manifest = "Cargo.toml"
+    )]
+    root: PathBuf,
+    #[layout(nested)]
+    cache: Cache,
+}
+
+fn main() {
+    let layout = Layout {
+        root: "/srv/app".into(),
+        cache: Cache {
+            dir: "/var/cache/app".into(),
+        },
+    };
+
+    println!("{:?}", layout.build_log("crate", "42"));
+    layout.with_manifest(|path| println!("{path:?}"));
+    println!("{:?}", layout.cache.artifact("3f2a"));
+    layout.visit_roots(&mut |root| println!("root: {root:?}"));
+}
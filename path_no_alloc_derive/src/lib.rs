@@ -0,0 +1,309 @@
+//! Provides `#[derive(PathLayout)]` for
+//! [`path_no_alloc`](https://docs.rs/path_no_alloc/). Enable the `derive`
+//! feature of `path_no_alloc` and use it from there, rather than depending
+//! on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr};
+
+/// Declares a directory layout once, and generates accessors that join its
+/// paths without allocating.
+///
+/// Mark each field that holds a root path (anything that's
+/// `AsRef<Path>`) with `#[layout(name = "relative/{placeholder}/path")]`,
+/// listing as many accessors as it needs. Each accessor generates two
+/// methods:
+///
+/// - `name(&self, placeholder...) -> StackPathBuf`
+/// - `with_name(&self, placeholder..., f: impl FnOnce(&Path) -> R) -> R`,
+///   which joins the path in a stack buffer and passes it to `f`.
+///
+/// Placeholders become arguments of type `impl AsRef<Path>`, in the order
+/// they first appear, and are rendered the same way as with `PathTemplate`.
+/// `{{` and `}}` stand for literal braces.
+///
+/// Mark a root with no accessors with `#[layout(root)]`, and a field that's
+/// itself a `PathLayout` with `#[layout(nested)]`. The derived
+/// `PathLayout::visit_roots` visits every root, including nested ones.
+///
+/// ```rust,ignore
+/// use std::path::PathBuf;
+/// use path_no_alloc::PathLayout;
+///
+/// #[derive(PathLayout)]
+/// struct Cache {
+///     #[layout(artifact = "{hash}/artifact.bin")]
+///     dir: PathBuf,
+/// }
+///
+/// #[derive(PathLayout)]
+/// struct Layout {
+///     #[layout(build_log = "builds/{id}.log", manifest = "Cargo.toml")]
+///     root: PathBuf,
+///     #[layout(nested)]
+///     cache: Cache,
+/// }
+///
+/// let layout = Layout {
+///     root: "/srv/app".into(),
+///     cache: Cache { dir: "/var/cache/app".into() },
+/// };
+/// assert_eq!(layout.build_log("42"), std::path::Path::new("/srv/app/builds/42.log"));
+/// layout.with_manifest(|path| assert!(path.ends_with("Cargo.toml")));
+/// ```
+#[proc_macro_derive(PathLayout, attributes(layout))]
+pub fn derive_path_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+enum Role {
+    Root(Vec<(Ident, LitStr)>),
+    Nested,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "PathLayout can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "PathLayout can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut methods = Vec::new();
+    let mut visits = Vec::new();
+    for field in fields {
+        let Some(role) = field_role(field)? else {
+            continue;
+        };
+        let field_name = field.ident.as_ref().unwrap();
+        match role {
+            Role::Nested => visits.push(quote! {
+                ::path_no_alloc::PathLayout::visit_roots(&self.#field_name, visit);
+            }),
+            Role::Root(accessors) => {
+                visits.push(quote! {
+                    visit(::core::convert::AsRef::<::std::path::Path>::as_ref(&self.#field_name));
+                });
+                for (name, template) in accessors {
+                    methods.push(accessor(field_name, &name, &template)?);
+                }
+            }
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#methods)*
+        }
+
+        impl #impl_generics ::path_no_alloc::PathLayout for #name #ty_generics #where_clause {
+            fn visit_roots(&self, visit: &mut dyn FnMut(&::std::path::Path)) {
+                #(#visits)*
+            }
+        }
+    })
+}
+
+/// Reads a field's `#[layout(...)]` attributes.
+fn field_role(field: &syn::Field) -> syn::Result<Option<Role>> {
+    let mut role = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("layout") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("nested") {
+                if role.is_some() {
+                    return Err(meta.error("a nested layout can't also be a root"));
+                }
+                role = Some(Role::Nested);
+                return Ok(());
+            }
+            let accessors = match &mut role {
+                None => match role.insert(Role::Root(Vec::new())) {
+                    Role::Root(accessors) => accessors,
+                    Role::Nested => unreachable!(),
+                },
+                Some(Role::Root(accessors)) => accessors,
+                Some(Role::Nested) => {
+                    return Err(meta.error("a nested layout can't also be a root"))
+                }
+            };
+            if meta.path.is_ident("root") {
+                return Ok(());
+            }
+            let Some(name) = meta.path.get_ident() else {
+                return Err(meta.error("expected `root`, `nested`, or `name = \"path\"`"));
+            };
+            accessors.push((name.clone(), meta.value()?.parse()?));
+            Ok(())
+        })?;
+    }
+    Ok(role)
+}
+
+enum Piece {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// Generates the two methods for one accessor.
+fn accessor(field: &Ident, name: &Ident, template: &LitStr) -> syn::Result<TokenStream2> {
+    let segments = parse_template(template)?;
+
+    let mut args: Vec<Ident> = Vec::new();
+    let mut parts = Vec::new();
+    for segment in &segments {
+        for (i, piece) in segment.iter().enumerate() {
+            let part = match piece {
+                Piece::Literal(literal) if i == 0 => quote! {
+                    Segment(::std::path::Path::new(#literal))
+                },
+                Piece::Literal(literal) => quote! {
+                    Append(::std::ffi::OsStr::new(#literal))
+                },
+                Piece::Placeholder(placeholder) => {
+                    let arg = Ident::new(placeholder, template.span());
+                    if !args.contains(&arg) {
+                        args.push(arg.clone());
+                    }
+                    if i == 0 {
+                        quote! { Segment(#arg.as_ref()) }
+                    } else {
+                        quote! { Append(#arg.as_ref().as_os_str()) }
+                    }
+                }
+            };
+            parts.push(quote! { ::path_no_alloc::__private::LayoutPart::#part });
+        }
+    }
+
+    let with_name = format_ident!("with_{}", name);
+    // So that it can't clash with a placeholder
+    let f = Ident::new("f", Span::mixed_site());
+    let doc = format!("`{}` joined with `{}`.", field, template.value());
+    Ok(quote! {
+        #[doc = #doc]
+        pub fn #name(
+            &self,
+            #(#args: impl ::core::convert::AsRef<::std::path::Path>,)*
+        ) -> ::path_no_alloc::StackPathBuf {
+            self.#with_name(#(#args,)* |path| ::path_no_alloc::StackPathBuf::from_path(path))
+        }
+
+        #[doc = #doc]
+        ///
+        /// The path is joined in a stack buffer and passed to `f`.
+        pub fn #with_name<R>(
+            &self,
+            #(#args: impl ::core::convert::AsRef<::std::path::Path>,)*
+            #f: impl FnOnce(&::std::path::Path) -> R,
+        ) -> R {
+            ::path_no_alloc::__private::join_layout(
+                &[
+                    ::path_no_alloc::__private::LayoutPart::Segment(
+                        ::core::convert::AsRef::<::std::path::Path>::as_ref(&self.#field),
+                    ),
+                    #(#parts,)*
+                ],
+                #f,
+            )
+        }
+    })
+}
+
+/// Splits a template into segments of literals and placeholders, with the
+/// same syntax as `PathTemplate`. Templates are relative to their root, so
+/// they can't start with `/`.
+fn parse_template(template: &LitStr) -> syn::Result<Vec<Vec<Piece>>> {
+    let value = template.value();
+    let error = |message: String| syn::Error::new(template.span(), message);
+    if value.starts_with('/') {
+        return Err(error(
+            "layout paths are relative to their root, so can't start with `/`".into(),
+        ));
+    }
+
+    let mut segments = vec![Vec::new()];
+    let mut literal = String::new();
+    fn flush(segment: &mut Vec<Piece>, literal: &mut String) {
+        if !literal.is_empty() {
+            segment.push(Piece::Literal(std::mem::take(literal)));
+        }
+    }
+
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        let segment = segments.last_mut().unwrap();
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => literal.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => literal.push('}'),
+            '}' => {
+                return Err(error(
+                    "`}` without a matching `{`; use `}}` for a literal `}`".into(),
+                ))
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(error("`{` without a matching `}`".into())),
+                    }
+                }
+                if !is_valid_name(&name) {
+                    return Err(error(format!("invalid placeholder name `{name}`")));
+                }
+                if literal.is_empty() {
+                    if let Some(Piece::Placeholder(previous)) = segment.last() {
+                        return Err(error(format!(
+                            "placeholders `{previous}` and `{name}` are adjacent"
+                        )));
+                    }
+                }
+                flush(segment, &mut literal);
+                segment.push(Piece::Placeholder(name));
+            }
+            '/' => {
+                flush(segment, &mut literal);
+                if segment.is_empty() {
+                    return Err(error("layout path has an empty segment".into()));
+                }
+                segments.push(Vec::new());
+            }
+            c => literal.push(c),
+        }
+    }
+    let segment = segments.last_mut().unwrap();
+    flush(segment, &mut literal);
+    if segment.is_empty() {
+        return Err(error("layout path has an empty segment".into()));
+    }
+    Ok(segments)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    matches!(bytes.next(), Some(b'a'..=b'z' | b'A'..=b'Z' | b'_'))
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        && syn::parse_str::<Ident>(name).is_ok()
+}
@@ -0,0 +1,107 @@
+use std::{
+    env,
+    ffi::CStr,
+    io,
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{buffer::Writer, StackPathBuf};
+
+/// Makes `path` absolute by joining it onto the current directory. If the
+/// result fits inside the given buffer, uses the buffer. Otherwise, uses the
+/// given pathbuff.
+///
+/// The current directory is read with `getcwd` straight into the buffer, and
+/// only read with `env::current_dir` if it doesn't fit. An absolute `path`
+/// doesn't look at the current directory at all.
+///
+/// The result is the same as `std::path::absolute`'s: `.` components and
+/// repeated `/` are dropped, while a trailing `/` and a leading `//` are
+/// kept. `..` components are kept too, since resolving them lexically can
+/// change which file the path refers to when symlinks are involved. Use
+/// [`StackPathBuf::normalize`] on the result to resolve them anyway. An
+/// empty `path` fails with `InvalidInput`.
+pub fn absolute_in_buff<'a>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    path_buff: &'a mut Option<PathBuf>,
+    path: &Path,
+) -> io::Result<&'a Path> {
+    if path.as_os_str().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot make an empty path absolute",
+        ));
+    }
+    let bytes = path.as_os_str().as_bytes();
+    let mut out = Writer::new(raw_buff);
+    if !path.is_absolute() {
+        let fits = unsafe { out.write_stack_with(getcwd) };
+        if !fits {
+            out.extend(env::current_dir()?.as_os_str().as_bytes());
+        }
+    } else if bytes.starts_with(b"//") && !bytes.starts_with(b"///") {
+        // POSIX leaves exactly two leading slashes up to the implementation
+        out.extend(b"//");
+    } else {
+        out.push(b'/');
+    }
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => out.push_segment(name.as_bytes(), b'/'),
+            Component::ParentDir => out.push_segment(b"..", b'/'),
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    // A trailing slash means the path has to be a directory
+    if bytes.ends_with(b"/") && out.last() != Some(b'/') {
+        out.push(b'/');
+    }
+    Ok(out.finish_path(path_buff))
+}
+
+/// Calls `getcwd` on `buff`, returning the length of the current directory,
+/// or `None` if it doesn't fit.
+fn getcwd(buff: &mut [MaybeUninit<u8>]) -> Option<usize> {
+    let ptr = buff.as_mut_ptr() as *mut libc::c_char;
+    if unsafe { libc::getcwd(ptr, buff.len()) }.is_null() {
+        return None;
+    }
+    let cwd = unsafe { CStr::from_ptr(ptr) };
+    Some(cwd.to_bytes().len())
+}
+
+/// Appends the current directory to `out`, reading it with `getcwd` straight
+/// into `out`'s inline buffer if it fits.
+pub(crate) fn current_dir_into<const N: usize>(out: &mut StackPathBuf<N>) -> io::Result<()> {
+    if !unsafe { out.write_inline_with(getcwd) } {
+        out.extend(env::current_dir()?.as_os_str().as_bytes());
+    }
+    Ok(())
+}
+
+/// Like [`absolute_in_buff`], but returns an owned [`StackPathBuf`].
+///
+/// ```rust
+/// use path_no_alloc::absolute;
+///
+/// let mut path = absolute("src/../Cargo.toml").unwrap();
+/// assert!(path.is_absolute());
+///
+/// path.normalize();
+/// assert_eq!(path, std::env::current_dir().unwrap().join("Cargo.toml"));
+/// ```
+pub fn absolute(path: impl AsRef<Path>) -> io::Result<StackPathBuf> {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    absolute_in_buff(&mut raw_buff, &mut path_buff, path.as_ref()).map(StackPathBuf::from_path)
+}
+
+/// Like [`absolute`], but joins `path` onto `base` instead of the current
+/// directory. If `base` is relative, so is the result.
+pub fn absolute_from(base: impl AsRef<Path>, path: impl AsRef<Path>) -> StackPathBuf {
+    let mut result = StackPathBuf::from_path(base);
+    result.push(path);
+    result
+}
@@ -0,0 +1,215 @@
+use std::{ffi::CStr, io, mem::MaybeUninit};
+
+#[cfg(target_family = "unix")]
+use std::{
+    ffi::{OsStr, OsString},
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
+/// Byte writer backed by a caller-provided stack buffer. If the bytes written
+/// don't fit in the stack buffer, the writer spills onto the heap and keeps
+/// going there.
+///
+/// The last byte of the stack buffer is always kept free so that the finished
+/// result can be null-terminated, the same as with `join_in_buff`.
+pub(crate) struct Writer<'a> {
+    stack: &'a mut [MaybeUninit<u8>],
+    len: usize,
+    heap: Option<Vec<u8>>,
+}
+
+impl<'a> Writer<'a> {
+    pub(crate) fn new(stack: &'a mut [MaybeUninit<u8>]) -> Self {
+        Writer {
+            stack,
+            len: 0,
+            heap: None,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match &self.heap {
+            Some(heap) => heap.len(),
+            None => self.len,
+        }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        match &self.heap {
+            Some(heap) => heap,
+            None => unsafe {
+                std::slice::from_raw_parts(self.stack.as_ptr() as *const u8, self.len)
+            },
+        }
+    }
+
+    /// Makes sure that `additional` more bytes can be written. If they won't
+    /// fit in the stack buffer, this moves everything onto the heap.
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        match &mut self.heap {
+            Some(heap) => heap.reserve(additional),
+            None if self.len + additional < self.stack.len() => {}
+            None => {
+                let mut heap = Vec::with_capacity((self.len + additional).max(self.len * 2));
+                heap.extend_from_slice(self.as_bytes());
+                self.heap = Some(heap);
+            }
+        }
+    }
+
+    pub(crate) fn extend(&mut self, bytes: &[u8]) {
+        self.reserve(bytes.len());
+        match &mut self.heap {
+            Some(heap) => heap.extend_from_slice(bytes),
+            None => {
+                let len = bytes.len();
+                unsafe {
+                    core::ptr::copy_nonoverlapping(
+                        bytes.as_ptr(),
+                        self.stack[self.len..].as_mut_ptr() as *mut u8,
+                        len,
+                    );
+                }
+                self.len += len;
+            }
+        }
+    }
+
+    /// Lets `f` write straight into the unused part of the stack buffer. `f`
+    /// returns how many bytes it wrote, which must leave at least one byte
+    /// free, or `None` if they didn't fit. Returns false without calling `f`
+    /// if the writer has already spilled onto the heap.
+    ///
+    /// # Safety
+    ///
+    /// `f` must have initialized as many bytes as it says it wrote.
+    #[cfg(target_family = "unix")]
+    pub(crate) unsafe fn write_stack_with(
+        &mut self,
+        f: impl FnOnce(&mut [MaybeUninit<u8>]) -> Option<usize>,
+    ) -> bool {
+        if self.heap.is_some() {
+            return false;
+        }
+        match f(&mut self.stack[self.len..]) {
+            Some(len) => {
+                debug_assert!(self.len + len < self.stack.len());
+                self.len += len;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn push(&mut self, byte: u8) {
+        self.extend(&[byte])
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        match &mut self.heap {
+            Some(heap) => heap.truncate(len),
+            None => self.len = self.len.min(len),
+        }
+    }
+
+    /// Drops the first `len` bytes, shifting the rest down.
+    pub(crate) fn remove_prefix(&mut self, len: usize) {
+        match &mut self.heap {
+            Some(heap) => {
+                heap.drain(..len);
+            }
+            None => {
+                let len = len.min(self.len);
+                unsafe {
+                    let start = self.stack.as_mut_ptr();
+                    core::ptr::copy(start.add(len), start, self.len - len);
+                }
+                self.len -= len;
+            }
+        }
+    }
+
+    pub(crate) fn last(&self) -> Option<u8> {
+        self.as_bytes().last().copied()
+    }
+
+    /// Pushes a segment using the same rules as `PathBuf::push` on unix:
+    /// a segment starting with `sep` replaces everything written so far,
+    /// otherwise it's appended, with `sep` inserted if needed.
+    pub(crate) fn push_segment(&mut self, segment: &[u8], sep: u8) {
+        if segment.first() == Some(&sep) {
+            self.truncate(0);
+        } else if self.len() > 0 && self.last() != Some(sep) {
+            self.push(sep);
+        }
+        self.extend(segment)
+    }
+
+    /// Returns the written bytes. If they're on the stack, they're followed by
+    /// a null terminator. Otherwise they're moved into `heap_buff`.
+    pub(crate) fn finish_bytes(self, heap_buff: &'a mut Option<Vec<u8>>) -> &'a [u8] {
+        let Writer { stack, len, heap } = self;
+        match heap {
+            Some(heap) => heap_buff.insert(heap),
+            None => {
+                if let Some(end) = stack.get_mut(len) {
+                    end.write(b'\0');
+                }
+                unsafe { std::slice::from_raw_parts(stack.as_ptr() as *const u8, len) }
+            }
+        }
+    }
+
+    /// Same as [`Writer::finish_bytes`], but for text.
+    ///
+    /// # Safety
+    ///
+    /// Everything written must be valid UTF-8.
+    pub(crate) unsafe fn finish_str(self, str_buff: &'a mut Option<String>) -> &'a str {
+        let Writer { stack, len, heap } = self;
+        match heap {
+            Some(heap) => str_buff.insert(String::from_utf8_unchecked(heap)),
+            None => {
+                if let Some(end) = stack.get_mut(len) {
+                    end.write(b'\0');
+                }
+                let bytes = std::slice::from_raw_parts(stack.as_ptr() as *const u8, len);
+                std::str::from_utf8_unchecked(bytes)
+            }
+        }
+    }
+
+    /// Calls `f` with the written bytes as a null-terminated string. Fails
+    /// with `InvalidInput` if the bytes contain a null byte.
+    pub(crate) fn with_c_str<R>(
+        &mut self,
+        f: impl FnOnce(&CStr) -> io::Result<R>,
+    ) -> io::Result<R> {
+        let len = self.len();
+        self.push(b'\0');
+        let result = match CStr::from_bytes_with_nul(&self.as_bytes()[..=len]) {
+            Ok(c_str) => f(c_str),
+            Err(err) => Err(io::Error::new(io::ErrorKind::InvalidInput, err)),
+        };
+        self.truncate(len);
+        result
+    }
+
+    /// Returns the written path. If it's on the stack, it's followed by a null
+    /// terminator. Otherwise it's moved into `path_buff`.
+    #[cfg(target_family = "unix")]
+    pub(crate) fn finish_path(self, path_buff: &'a mut Option<PathBuf>) -> &'a Path {
+        let Writer { stack, len, heap } = self;
+        match heap {
+            Some(heap) => path_buff.insert(OsString::from_vec(heap).into()),
+            None => {
+                if let Some(end) = stack.get_mut(len) {
+                    end.write(b'\0');
+                }
+                let bytes = unsafe { std::slice::from_raw_parts(stack.as_ptr() as *const u8, len) };
+                OsStr::from_bytes(bytes).as_ref()
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    fmt,
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use crate::buffer::Writer;
+
+/// Decides what [`join_confined`] does with segments that would otherwise
+/// leave the root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Confinement {
+    /// Absolute segments and `..` components that would climb above the root
+    /// are rejected with an error.
+    Strict,
+    /// Absolute segments are re-rooted under the root, and `..` components
+    /// stop at the root.
+    Clamp,
+}
+
+/// The reason a confined join was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConfineError {
+    /// A segment after the root was absolute.
+    Absolute,
+    /// A `..` component would have climbed above the root.
+    Escape,
+    /// A segment contained a null byte.
+    Nul,
+}
+
+impl fmt::Display for ConfineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfineError::Absolute => "absolute path segment is not allowed here",
+            ConfineError::Escape => "path segment escapes the root",
+            ConfineError::Nul => "path segment contains a null byte",
+        })
+    }
+}
+
+impl std::error::Error for ConfineError {}
+
+/// Joins N paths, treating the first one as a jail that the rest can't leave.
+///
+/// The root is used as-is. Every segment after it is resolved lexically:
+/// `.` and empty components are dropped, and `..` removes the previous
+/// component. What happens to absolute segments and `..` components that
+/// would climb above the root depends on `confinement`. Segments containing
+/// null bytes are always rejected.
+///
+/// On success, the result is the root followed by zero or more normal
+/// components. Like `join_in_buff`, the result is written into `raw_buff` if
+/// it fits, and into `path_buff` otherwise.
+pub fn join_confined<'a, const N: usize>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    path_buff: &'a mut Option<PathBuf>,
+    confinement: Confinement,
+    paths: [&Path; N],
+) -> Result<&'a Path, ConfineError> {
+    let mut out = Writer::new(raw_buff);
+
+    if let Some((root, paths)) = paths.split_first() {
+        let root = root.as_os_str().as_bytes();
+        if root.contains(&b'\0') {
+            return Err(ConfineError::Nul);
+        }
+        out.extend(root);
+        push_confined(&mut out, root.len(), confinement, paths)?;
+    }
+
+    Ok(out.finish_path(path_buff))
+}
+
+/// Pushes each segment onto `out` without ever going below `root_len`.
+pub(crate) fn push_confined(
+    out: &mut Writer,
+    root_len: usize,
+    confinement: Confinement,
+    paths: &[&Path],
+) -> Result<(), ConfineError> {
+    for path in paths {
+        let path = path.as_os_str().as_bytes();
+        if path.contains(&b'\0') {
+            return Err(ConfineError::Nul);
+        }
+        if path.first() == Some(&b'/') && confinement == Confinement::Strict {
+            return Err(ConfineError::Absolute);
+        }
+
+        for component in path.split(|b| *b == b'/') {
+            match component {
+                b"" | b"." => {}
+                b".." => {
+                    if out.len() <= root_len {
+                        match confinement {
+                            Confinement::Strict => return Err(ConfineError::Escape),
+                            Confinement::Clamp => continue,
+                        }
+                    }
+                    let bytes = out.as_bytes();
+                    let parent = bytes.iter().rposition(|b| *b == b'/').unwrap_or(0);
+                    out.truncate(parent.max(root_len));
+                }
+                component => {
+                    if out.len() > 0 && out.last() != Some(b'/') {
+                        out.push(b'/');
+                    }
+                    out.extend(component);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Like `with_paths!`, except that the first path in each declaration is a
+/// root that the remaining paths can't escape. Each declared variable is a
+/// `Result<&Path, ConfineError>`, computed with [`join_confined`] using
+/// [`Confinement::Strict`].
+///
+/// ```rust
+/// use path_no_alloc::{with_confined_paths, ConfineError};
+/// use std::path::Path;
+///
+/// let root = "/srv/uploads";
+/// let good = "user/avatar.png";
+/// let bad = "../../etc/passwd";
+///
+/// with_confined_paths! {
+///     good = root / good,
+///     bad = root / bad
+/// };
+///
+/// assert_eq!(good, Ok(Path::new("/srv/uploads/user/avatar.png")));
+/// assert_eq!(bad, Err(ConfineError::Escape));
+/// ```
+#[macro_export]
+macro_rules! with_confined_paths {
+    // Called by `__with_joined_paths!` for each declaration
+    { @join $arr:ident, $buff:ident, $( $path:ident ) / + } => {
+        $crate::join_confined(&mut $arr, &mut $buff, $crate::Confinement::Strict, [$($path.as_ref()),+])
+    };
+    { $($tokens:tt)* } => {
+        $crate::__with_joined_paths!(with_confined_paths; $($tokens)*)
+    };
+}
@@ -0,0 +1,161 @@
+use std::{
+    ffi::CStr,
+    fs::{File, OpenOptions},
+    io,
+    mem::MaybeUninit,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+        unix::fs::OpenOptionsExt,
+    },
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{buffer::Writer, confined::push_confined, sys::cvt, Confinement};
+
+/// Set to false the first time `openat2` turns out to be missing, so that we
+/// don't keep asking the kernel for it.
+static HAS_OPENAT2: AtomicBool = AtomicBool::new(true);
+
+/// An open directory, used as the root for [`Dir::open_beneath`].
+#[derive(Debug)]
+pub struct Dir {
+    fd: OwnedFd,
+}
+
+impl Dir {
+    /// Opens the directory at `path`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Dir> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECTORY)
+            .open(path)?;
+        Ok(Dir { fd: file.into() })
+    }
+
+    /// Opens a file for reading, by joining `segments` beneath this directory.
+    ///
+    /// The segments are untrusted: they're joined with
+    /// [`Confinement::Strict`], so absolute segments and `..` components that
+    /// would leave the directory are rejected with `InvalidInput`. In
+    /// addition, no symlinks are followed at any point during resolution, so
+    /// a symlink planted under the directory can't be used to escape it.
+    ///
+    /// On Linux 5.6 and later this is a single `openat2` call with
+    /// `RESOLVE_BENEATH | RESOLVE_NO_SYMLINKS`. On older kernels, each
+    /// component is opened in turn with `O_NOFOLLOW`.
+    pub fn open_beneath<const N: usize>(&self, segments: [&Path; N]) -> io::Result<File> {
+        self.open_beneath_with(
+            &segments,
+            libc::O_RDONLY,
+            HAS_OPENAT2.load(Ordering::Relaxed),
+        )
+        .map(File::from)
+    }
+
+    /// Same as [`Dir::open_beneath`], but opens a directory.
+    pub fn open_dir_beneath<const N: usize>(&self, segments: [&Path; N]) -> io::Result<Dir> {
+        self.open_beneath_with(&segments, DIR_FLAGS, HAS_OPENAT2.load(Ordering::Relaxed))
+            .map(|fd| Dir { fd })
+    }
+
+    pub(crate) fn open_beneath_with(
+        &self,
+        segments: &[&Path],
+        flags: libc::c_int,
+        use_openat2: bool,
+    ) -> io::Result<OwnedFd> {
+        let mut raw_buff: [MaybeUninit<u8>; 256] = [MaybeUninit::uninit(); 256];
+        let mut path = Writer::new(&mut raw_buff);
+        push_confined(&mut path, 0, Confinement::Strict, segments)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        if path.len() == 0 {
+            path.push(b'.');
+        }
+
+        if use_openat2 {
+            match path.with_c_str(|path| self.openat2(path, flags)) {
+                Err(err) if err.raw_os_error() == Some(libc::ENOSYS) => {
+                    HAS_OPENAT2.store(false, Ordering::Relaxed);
+                }
+                result => return result,
+            }
+        }
+        self.open_walk(path.as_bytes(), flags)
+    }
+
+    fn openat2(&self, path: &CStr, flags: libc::c_int) -> io::Result<OwnedFd> {
+        let mut how: libc::open_how = unsafe { std::mem::zeroed() };
+        how.flags = (flags | libc::O_CLOEXEC) as u64;
+        how.resolve =
+            libc::RESOLVE_BENEATH | libc::RESOLVE_NO_SYMLINKS | libc::RESOLVE_NO_MAGICLINKS;
+
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_openat2,
+                self.fd.as_raw_fd(),
+                path.as_ptr(),
+                &how as *const libc::open_how,
+                std::mem::size_of::<libc::open_how>(),
+            )
+        };
+        cvt(fd as libc::c_int).map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    /// Opens a relative, already normalized path one component at a time,
+    /// refusing to follow symlinks at each step.
+    fn open_walk(&self, path: &[u8], flags: libc::c_int) -> io::Result<OwnedFd> {
+        let mut raw_buff: [MaybeUninit<u8>; 256] = [MaybeUninit::uninit(); 256];
+        let mut name = Writer::new(&mut raw_buff);
+
+        let mut current: Option<OwnedFd> = None;
+        let mut components = path.split(|b| *b == b'/').peekable();
+        while let Some(component) = components.next() {
+            let flags = match components.peek() {
+                Some(_) => DIR_FLAGS,
+                None => flags,
+            };
+            let parent = current.as_ref().unwrap_or(&self.fd).as_raw_fd();
+
+            name.truncate(0);
+            name.extend(component);
+            let fd = name.with_c_str(|name| {
+                cvt(unsafe {
+                    libc::openat(
+                        parent,
+                        name.as_ptr(),
+                        flags | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+                    )
+                })
+            })?;
+            current = Some(unsafe { OwnedFd::from_raw_fd(fd) });
+        }
+        current.ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))
+    }
+}
+
+const DIR_FLAGS: libc::c_int = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+
+impl AsFd for Dir {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for Dir {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl From<OwnedFd> for Dir {
+    fn from(fd: OwnedFd) -> Dir {
+        Dir { fd }
+    }
+}
+
+impl From<Dir> for OwnedFd {
+    fn from(dir: Dir) -> OwnedFd {
+        dir.fd
+    }
+}
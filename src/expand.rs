@@ -0,0 +1,252 @@
+use std::{
+    env,
+    ffi::{CStr, OsStr, OsString},
+    fmt,
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use crate::buffer::Writer;
+
+/// Controls how [`expand_in_buff`] expands paths.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ExpandOptions {
+    /// Reject `~user` instead of looking the user up in the password
+    /// database. `~` on its own still expands to `$HOME`. Defaults to
+    /// `false`.
+    pub strict: bool,
+}
+
+/// The reason a path couldn't be expanded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ExpandError {
+    /// A `$VAR` or `${VAR}` referred to a variable that isn't set.
+    UndefinedVariable(OsString),
+    /// A `~` was used, but `HOME` isn't set.
+    NoHome,
+    /// A `~user` named a user that doesn't exist.
+    UnknownUser(OsString),
+    /// A `~user` was used in strict mode.
+    UserLookupDisabled(OsString),
+    /// A `${` had no matching `}`.
+    UnclosedBrace,
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpandError::UndefinedVariable(name) => {
+                write!(
+                    f,
+                    "environment variable `{}` is not set",
+                    name.to_string_lossy()
+                )
+            }
+            ExpandError::NoHome => f.write_str("`~` used but `HOME` is not set"),
+            ExpandError::UnknownUser(name) => {
+                write!(f, "unknown user `{}`", name.to_string_lossy())
+            }
+            ExpandError::UserLookupDisabled(name) => write!(
+                f,
+                "`~{}` used, but user lookups are disabled",
+                name.to_string_lossy()
+            ),
+            ExpandError::UnclosedBrace => f.write_str("`${` without a matching `}`"),
+        }
+    }
+}
+
+impl std::error::Error for ExpandError {}
+
+/// Expands N paths and joins them. If the result fits inside the given
+/// buffer, uses the buffer. Otherwise, uses the given pathbuff.
+///
+/// Each path is expanded the way a shell would, before it's joined:
+///
+/// - A leading `~` becomes `$HOME`, and a leading `~user` becomes that
+///   user's home directory.
+/// - `$VAR` and `${VAR}` become the value of the variable, and it's an error
+///   if it isn't set.
+/// - `${VAR:-default}` becomes `default` if the variable isn't set or is
+///   empty. The default is used as-is, without being expanded itself.
+///
+/// A `$` that isn't followed by a variable name is kept. Values are written
+/// straight into the buffer, and the joining rules are the same as
+/// `join_in_buff`'s, applied after expansion: a path that expands to an
+/// absolute path replaces everything before it.
+pub fn expand_in_buff<'a, const N: usize>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    path_buff: &'a mut Option<PathBuf>,
+    options: &ExpandOptions,
+    paths: [&Path; N],
+) -> Result<&'a Path, ExpandError> {
+    expand_in_buff_with(raw_buff, path_buff, options, paths, |name| {
+        env::var_os(name)
+    })
+}
+
+/// Like [`expand_in_buff`], but looks variables up with `lookup` instead of
+/// reading the environment. `HOME` is looked up the same way.
+pub fn expand_in_buff_with<'a, const N: usize>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    path_buff: &'a mut Option<PathBuf>,
+    options: &ExpandOptions,
+    paths: [&Path; N],
+    mut lookup: impl FnMut(&OsStr) -> Option<OsString>,
+) -> Result<&'a Path, ExpandError> {
+    let mut out = Writer::new(raw_buff);
+    out.reserve(paths.iter().map(|p| p.as_os_str().len() + 1).sum());
+    for path in paths {
+        if out.len() > 0 && out.last() != Some(b'/') {
+            out.push(b'/');
+        }
+        let start = out.len();
+        expand_one(&mut out, path.as_os_str().as_bytes(), options, &mut lookup)?;
+        if out.as_bytes().get(start) == Some(&b'/') {
+            out.remove_prefix(start);
+        }
+    }
+    Ok(out.finish_path(path_buff))
+}
+
+fn expand_one(
+    out: &mut Writer,
+    mut path: &[u8],
+    options: &ExpandOptions,
+    lookup: &mut impl FnMut(&OsStr) -> Option<OsString>,
+) -> Result<(), ExpandError> {
+    if let Some(rest) = path.strip_prefix(b"~") {
+        let end = rest.iter().position(|&b| b == b'/').unwrap_or(rest.len());
+        let user = &rest[..end];
+        if user.is_empty() {
+            let home = lookup(OsStr::new("HOME")).ok_or(ExpandError::NoHome)?;
+            out.extend(home.as_bytes());
+        } else if options.strict {
+            return Err(ExpandError::UserLookupDisabled(bytes_to_os(user)));
+        } else {
+            push_user_home(out, user)?;
+        }
+        path = &rest[end..];
+    }
+
+    while let Some(dollar) = path.iter().position(|&b| b == b'$') {
+        out.extend(&path[..dollar]);
+        let rest = &path[dollar + 1..];
+
+        if let Some(braced) = rest.strip_prefix(b"{") {
+            let end = braced
+                .iter()
+                .position(|&b| b == b'}')
+                .ok_or(ExpandError::UnclosedBrace)?;
+            let (name, default) = match braced[..end].windows(2).position(|w| w == b":-") {
+                Some(i) => (&braced[..i], Some(&braced[i + 2..end])),
+                None => (&braced[..end], None),
+            };
+            match (lookup(OsStr::from_bytes(name)), default) {
+                (Some(value), Some(default)) if value.is_empty() => out.extend(default),
+                (Some(value), _) => out.extend(value.as_bytes()),
+                (None, Some(default)) => out.extend(default),
+                (None, None) => return Err(ExpandError::UndefinedVariable(bytes_to_os(name))),
+            }
+            path = &braced[end + 1..];
+            continue;
+        }
+
+        let len = match rest.first() {
+            Some(b) if b.is_ascii_alphabetic() || *b == b'_' => rest
+                .iter()
+                .position(|b| !b.is_ascii_alphanumeric() && *b != b'_')
+                .unwrap_or(rest.len()),
+            _ => 0,
+        };
+        if len == 0 {
+            out.push(b'$');
+        } else {
+            let name = &rest[..len];
+            let value = lookup(OsStr::from_bytes(name))
+                .ok_or_else(|| ExpandError::UndefinedVariable(bytes_to_os(name)))?;
+            out.extend(value.as_bytes());
+        }
+        path = &rest[len..];
+    }
+    out.extend(path);
+    Ok(())
+}
+
+fn bytes_to_os(bytes: &[u8]) -> OsString {
+    OsStr::from_bytes(bytes).to_owned()
+}
+
+/// Writes the home directory of `user` from the password database.
+fn push_user_home(out: &mut Writer, user: &[u8]) -> Result<(), ExpandError> {
+    let unknown = || ExpandError::UnknownUser(bytes_to_os(user));
+
+    let mut name_buff = [0u8; 256];
+    if user.len() >= name_buff.len() || user.contains(&0) {
+        return Err(unknown());
+    }
+    name_buff[..user.len()].copy_from_slice(user);
+    let name = CStr::from_bytes_until_nul(&name_buff).map_err(|_| unknown())?;
+
+    let mut stack_buff = [0 as libc::c_char; 1024];
+    let mut heap_buff = Vec::new();
+    loop {
+        let buff = match heap_buff.is_empty() {
+            true => &mut stack_buff[..],
+            false => &mut heap_buff[..],
+        };
+        let mut passwd = MaybeUninit::<libc::passwd>::uninit();
+        let mut result = std::ptr::null_mut();
+        let err = unsafe {
+            libc::getpwnam_r(
+                name.as_ptr(),
+                passwd.as_mut_ptr(),
+                buff.as_mut_ptr(),
+                buff.len(),
+                &mut result,
+            )
+        };
+        if err == libc::ERANGE {
+            let len = buff.len() * 2;
+            heap_buff.resize(len, 0);
+            continue;
+        }
+        if err != 0 || result.is_null() {
+            return Err(unknown());
+        }
+        let dir = unsafe { CStr::from_ptr(passwd.assume_init_ref().pw_dir) };
+        out.extend(dir.to_bytes());
+        return Ok(());
+    }
+}
+
+/// Like `with_paths!`, but expands `~`, `~user` and environment variables in
+/// each path with [`expand_in_buff`] and the default [`ExpandOptions`]
+/// before joining. Each declared variable is a `Result<&Path, ExpandError>`.
+///
+/// ```rust
+/// use std::path::Path;
+/// use path_no_alloc::with_expanded_paths;
+///
+/// std::env::set_var("PATH_NO_ALLOC_PROJECT", "demo");
+/// let cache = "${XDG_CACHE_HOME:-/tmp/cache}";
+/// let project = "$PATH_NO_ALLOC_PROJECT";
+/// let file = "out";
+///
+/// with_expanded_paths! {
+///     out = cache / project / file
+/// };
+///
+/// assert!(out.unwrap().ends_with("demo/out"));
+/// ```
+#[macro_export]
+macro_rules! with_expanded_paths {
+    // Called by `__with_joined_paths!` for each declaration
+    { @join $arr:ident, $buff:ident, $( $path:ident ) / + } => {
+        $crate::expand_in_buff(&mut $arr, &mut $buff, &$crate::ExpandOptions::default(), [$($path.as_ref()),+])
+    };
+    { $($tokens:tt)* } => {
+        $crate::__with_joined_paths!(with_expanded_paths; $($tokens)*)
+    };
+}
@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use crate::{sys, StackPathBuf};
+
+/// Controls where [`find_up_with`] stops searching.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FindUp {
+    /// Don't search directories on a different filesystem than `start`.
+    /// Defaults to `false`.
+    pub same_file_system: bool,
+    /// Stop after searching the first directory that contains a `.git`
+    /// entry. Defaults to `false`.
+    pub stop_at_repository: bool,
+}
+
+/// Searches `start` and each of its ancestors for an entry named by one of
+/// `names`, and returns the path of the first one that exists. Within a
+/// directory, names are tried in order.
+///
+/// The candidate paths are all built in the same buffer, which only moves to
+/// the heap if a candidate doesn't fit inline, and existence is checked with
+/// `stat` directly on that buffer.
+///
+/// ```rust
+/// use path_no_alloc::find_up;
+///
+/// let manifest = find_up(env!("CARGO_MANIFEST_DIR"), &["Cargo.toml"]).unwrap();
+/// assert!(manifest.ends_with("Cargo.toml"));
+/// ```
+pub fn find_up<S: AsRef<Path>>(start: impl AsRef<Path>, names: &[S]) -> Option<StackPathBuf> {
+    find_up_with(start, names, &FindUp::default())
+}
+
+/// Like [`find_up`], but with control over where the search stops.
+pub fn find_up_with<S: AsRef<Path>>(
+    start: impl AsRef<Path>,
+    names: &[S],
+    options: &FindUp,
+) -> Option<StackPathBuf> {
+    let start = start.as_ref();
+    let mut path = StackPathBuf::from_path(start);
+    let mut device = None;
+
+    for ancestor in start.ancestors() {
+        let len = ancestor.as_os_str().len();
+        path.truncate(len);
+
+        if options.same_file_system {
+            // An empty ancestor is the current directory
+            let stat = if len == 0 {
+                sys::stat(c".", true)
+            } else {
+                path.stat()
+            };
+            let dev = stat.ok()?.dev();
+            if *device.get_or_insert(dev) != dev {
+                return None;
+            }
+        }
+
+        for name in names {
+            path.push(name);
+            if path.exists() {
+                return Some(path);
+            }
+            path.truncate(len);
+        }
+
+        if options.stop_at_repository {
+            path.push(".git");
+            if path.symlink_stat().is_ok() {
+                return None;
+            }
+        }
+    }
+    None
+}
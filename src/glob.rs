@@ -0,0 +1,80 @@
+use std::{ffi::OsStr, io, os::unix::ffi::OsStrExt, path::Path};
+
+use crate::{walk_with, Pattern, StackPathBuf, WalkEntry, WalkOptions, Walker};
+
+type Prune<'a> = Box<dyn FnMut(&WalkEntry<'_>) -> bool + 'a>;
+
+/// Expands `pattern` against the files under `root`. See [`GlobWalk`].
+///
+/// The pattern is matched against each path relative to `root`, and the
+/// yielded paths are joined onto `root`.
+///
+/// ```rust
+/// use path_no_alloc::{glob_walk, Pattern};
+///
+/// let pattern = Pattern::new("**/*.rs").unwrap();
+/// let mut matches = glob_walk("src", &pattern).unwrap();
+/// let mut found = false;
+/// while let Some(path) = matches.next() {
+///     let path = path.unwrap();
+///     assert_eq!(path.extension(), Some("rs".as_ref()));
+///     found |= path == std::path::Path::new("src/lib.rs");
+/// }
+/// assert!(found);
+/// ```
+pub fn glob_walk<'a>(root: impl AsRef<Path>, pattern: &'a Pattern) -> io::Result<GlobWalk<'a>> {
+    let root = StackPathBuf::<128>::from_path(root);
+    let mut root_len = root.len();
+    if root_len > 0 && root.as_bytes().last() != Some(&b'/') {
+        root_len += 1;
+    }
+    let prune: Prune<'a> = Box::new(move |entry| {
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && !pattern.could_match_within(relative(entry.path(), root_len))
+            && !pattern.matches(relative(entry.path(), root_len))
+    });
+    Ok(GlobWalk {
+        walker: walk_with(&root, &WalkOptions::default(), prune)?,
+        pattern,
+        root_len,
+    })
+}
+
+fn relative(path: &Path, root_len: usize) -> &Path {
+    Path::new(OsStr::from_bytes(&path.as_os_str().as_bytes()[root_len..]))
+}
+
+/// A lending iterator over the paths that match a [`Pattern`], built on
+/// [`Walker`].
+///
+/// Every candidate is joined in the walker's single path buffer, and
+/// directories are only descended into if something inside them could
+/// match, so expanding `src/**/*.rs` doesn't allocate per entry and doesn't
+/// read `target`. Since each path borrows that buffer, this isn't an
+/// `Iterator`: use `while let Some(path) = matches.next()`. Paths come in
+/// the order the filesystem returns them in, and symlinks aren't followed.
+pub struct GlobWalk<'a> {
+    walker: Walker<Prune<'a>>,
+    pattern: &'a Pattern,
+    // Length of the root's path, plus the separator
+    root_len: usize,
+}
+
+impl GlobWalk<'_> {
+    /// Finds the next matching path.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<io::Result<&Path>> {
+        loop {
+            let matched = match self.walker.next()? {
+                Err(err) => return Some(Err(err)),
+                Ok(entry) => {
+                    entry.depth() > 0 && self.pattern.matches(relative(entry.path(), self.root_len))
+                }
+            };
+            if matched {
+                return Some(Ok(self.walker.current_path()));
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    ffi::OsStr,
+    fs, io,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{Pattern, StackPathBuf};
+
+/// Matches paths against gitignore-style rules.
+///
+/// Rules use `.gitignore` syntax: blank lines and lines starting with `#` are
+/// skipped, `!` re-includes what an earlier rule excluded, a trailing `/`
+/// only matches directories, and a rule with a `/` anywhere but at the end is
+/// anchored to the directory the rules belong to, while one without matches
+/// at any depth. Patterns are globs as in [`Pattern`], without brace
+/// alternation, and `\` escapes a leading `#` or `!`, or trailing spaces.
+/// Lines that aren't valid globs are skipped, like git does.
+///
+/// Compiling the rules allocates; matching doesn't.
+///
+/// ```rust
+/// use path_no_alloc::{with_paths, Ignore};
+///
+/// let mut ignore = Ignore::new("project");
+/// ignore.add_rules("target/\n*.log\n!keep.log\n/TODO");
+///
+/// let (root, logs, name, kept, build) =
+///     ("project", "logs", "debug.log", "keep.log", "target/debug/app");
+/// with_paths! {
+///     log = root / logs / name,
+///     kept = root / kept,
+///     build = root / build
+/// };
+/// assert!(ignore.is_ignored(log, false));
+/// assert!(!ignore.is_ignored(kept, false));
+/// assert!(ignore.is_ignored(build, false));
+/// assert!(ignore.is_ignored("TODO", false));
+/// assert!(!ignore.is_ignored("docs/TODO", false));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Ignore {
+    root: PathBuf,
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl Ignore {
+    /// Creates a matcher with no rules, for paths under `root`.
+    pub fn new(root: impl AsRef<Path>) -> Ignore {
+        Ignore {
+            root: root.as_ref().to_owned(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Reads the rules in `dir/.gitignore` and then `dir/.ignore`, so that
+    /// the rules in `.ignore` take precedence. Either file can be missing.
+    pub fn from_dir(dir: impl AsRef<Path>) -> io::Result<Ignore> {
+        let mut ignore = Ignore::new(&dir);
+        let mut path = StackPathBuf::<128>::from_path(dir);
+        for name in [".gitignore", ".ignore"] {
+            path.push(name);
+            match fs::read_to_string(&path) {
+                Ok(rules) => ignore.add_rules(&rules),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+            path.pop();
+        }
+        Ok(ignore)
+    }
+
+    /// Adds the rules in `rules`, one per line. Later rules take precedence
+    /// over earlier ones.
+    pub fn add_rules(&mut self, rules: &str) {
+        self.rules.extend(rules.lines().filter_map(parse_rule));
+    }
+
+    /// The directory the rules belong to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns true if `path` is ignored. `path` is either relative to
+    /// [`Ignore::root`], or starts with it.
+    ///
+    /// Like git, a path inside an ignored directory is ignored too, even if a
+    /// later rule re-includes it, so every directory above `path` is checked
+    /// first.
+    ///
+    /// A run of `/` in `path` counts as one separator, as it does in
+    /// [`Pattern`] matching.
+    pub fn is_ignored(&self, path: impl AsRef<Path>, is_dir: bool) -> bool {
+        let path = path.as_ref();
+        let path = path.strip_prefix(&self.root).unwrap_or(path);
+        let bytes = path.as_os_str().as_bytes();
+        let parents = bytes
+            .iter()
+            .enumerate()
+            .filter(|&(i, &b)| b == b'/' && i > 0 && bytes[i - 1] != b'/')
+            .map(|(i, _)| &bytes[..i]);
+        for parent in parents {
+            if self.matched(Path::new(OsStr::from_bytes(parent)), true) {
+                return true;
+            }
+        }
+        self.matched(path, is_dir)
+    }
+
+    /// Returns whether the last rule that matches `path` itself ignores it.
+    fn matched(&self, path: &Path, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.pattern.matches(path))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    if line.starts_with('#') {
+        return None;
+    }
+    let line = trim_unescaped_spaces(line);
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    if line.is_empty() {
+        return None;
+    }
+
+    let pattern = if let Some(anchored) = line.strip_prefix('/') {
+        Pattern::compile(anchored, false)
+    } else if line.contains('/') {
+        Pattern::compile(line, false)
+    } else {
+        Pattern::compile(&format!("**/{line}"), false)
+    };
+    Some(Rule {
+        pattern: pattern.ok()?,
+        negated,
+        dir_only,
+    })
+}
+
+/// Trims trailing spaces, unless they're escaped with a `\`.
+fn trim_unescaped_spaces(line: &str) -> &str {
+    let mut end = line.trim_end_matches(' ').len();
+    if end < line.len() {
+        let backslashes = line[..end]
+            .bytes()
+            .rev()
+            .take_while(|&b| b == b'\\')
+            .count();
+        if backslashes % 2 == 1 {
+            end += 1;
+        }
+    }
+    &line[..end]
+}
@@ -0,0 +1,115 @@
+use std::{fmt, mem::MaybeUninit};
+
+use crate::buffer::Writer;
+
+/// Controls how [`join_key_in_buff`] treats separators and dot segments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyOptions {
+    /// Separator placed between segments. Defaults to `/`.
+    pub separator: char,
+    /// Strip leading and trailing separators from every segment, and
+    /// collapse repeated separators inside a segment. Defaults to `true`.
+    pub trim: bool,
+    /// Reject any `.` or `..` component. Defaults to `true`.
+    pub reject_dot_segments: bool,
+}
+
+impl Default for KeyOptions {
+    fn default() -> Self {
+        KeyOptions {
+            separator: '/',
+            trim: true,
+            reject_dot_segments: true,
+        }
+    }
+}
+
+/// The reason a key couldn't be built.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyError {
+    /// A segment contained a `.` or `..` component.
+    DotSegment,
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KeyError::DotSegment => "key segment contains a `.` or `..` component",
+        })
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+/// Joins N segments into an object-storage key. If the key fits inside the
+/// given buffer, uses the buffer. Otherwise, uses the given string.
+///
+/// Unlike paths, keys have no notion of being absolute: a leading separator
+/// never discards the segments before it. Empty segments are skipped, so
+/// they never produce a doubled separator.
+pub fn join_key_in_buff<'a, const N: usize>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    str_buff: &'a mut Option<String>,
+    options: &KeyOptions,
+    segments: [&str; N],
+) -> Result<&'a str, KeyError> {
+    let mut sep_buff = [0; 4];
+    let sep = options.separator.encode_utf8(&mut sep_buff);
+
+    let mut out = Writer::new(raw_buff);
+    out.reserve(segments.iter().map(|s| s.len() + sep.len()).sum());
+    for segment in segments {
+        if options.reject_dot_segments && segment.split(&*sep).any(|c| c == "." || c == "..") {
+            return Err(KeyError::DotSegment);
+        }
+
+        if options.trim {
+            for component in segment.split(&*sep).filter(|c| !c.is_empty()) {
+                if out.len() > 0 {
+                    out.extend(sep.as_bytes());
+                }
+                out.extend(component.as_bytes());
+            }
+        } else if !segment.is_empty() {
+            if out.len() > 0 {
+                out.extend(sep.as_bytes());
+            }
+            out.extend(segment.as_bytes());
+        }
+    }
+    // Every byte written came from one of the segments or the separator
+    Ok(unsafe { out.finish_str(str_buff) })
+}
+
+/// Like `with_paths!`, but builds object-storage keys with
+/// [`join_key_in_buff`] and the default [`KeyOptions`]. Each declared
+/// variable is a `Result<&str, KeyError>`. Anything that implements
+/// `AsRef<str>` can be joined.
+///
+/// ```rust
+/// use path_no_alloc::{with_keys, KeyError};
+///
+/// let prefix = "/bucket-prefix/";
+/// let tenant = "acme";
+/// let date = "";
+/// let object = "reports//q3.csv";
+/// let sneaky = "../other-tenant";
+///
+/// with_keys! {
+///     key = prefix / tenant / date / object,
+///     bad = prefix / sneaky
+/// };
+///
+/// assert_eq!(key, Ok("bucket-prefix/acme/reports/q3.csv"));
+/// assert_eq!(bad, Err(KeyError::DotSegment));
+/// ```
+#[macro_export]
+macro_rules! with_keys {
+    // Called by `__with_joined_paths!` for each declaration
+    { @join $arr:ident, $buff:ident, $( $segment:ident ) / + } => {
+        $crate::join_key_in_buff(&mut $arr, &mut $buff, &$crate::KeyOptions::default(), [$($segment.as_ref()),+])
+    };
+    { $($tokens:tt)* } => {
+        $crate::__with_joined_paths!(with_keys; $($tokens)*)
+    };
+}
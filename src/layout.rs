@@ -0,0 +1,66 @@
+use std::{ffi::OsStr, fs, io, mem::MaybeUninit, os::unix::ffi::OsStrExt, path::Path};
+
+use crate::buffer::Writer;
+
+/// A directory layout: a struct of root paths and nested layouts, whose
+/// accessors join paths without allocating.
+///
+/// Implement it with `#[derive(PathLayout)]`, which needs the `derive`
+/// feature. The derive also generates an accessor method for each path in
+/// the layout:
+///
+/// ```rust,ignore
+/// use std::path::PathBuf;
+/// use path_no_alloc::PathLayout;
+///
+/// #[derive(PathLayout)]
+/// struct Layout {
+///     #[layout(build_log = "builds/{id}.log", manifest = "Cargo.toml")]
+///     root: PathBuf,
+/// }
+///
+/// let layout = Layout { root: "/srv/app".into() };
+/// assert_eq!(layout.build_log("42"), std::path::Path::new("/srv/app/builds/42.log"));
+/// layout.with_manifest(|path| assert!(path.ends_with("Cargo.toml")));
+/// ```
+pub trait PathLayout {
+    /// Calls `visit` on each root path in the layout, including the roots of
+    /// nested layouts.
+    fn visit_roots(&self, visit: &mut dyn FnMut(&Path));
+
+    /// Creates every root directory in the layout, along with any missing
+    /// parents.
+    fn create_dirs(&self) -> io::Result<()> {
+        let mut result = Ok(());
+        self.visit_roots(&mut |root| {
+            if result.is_ok() {
+                result = fs::create_dir_all(root);
+            }
+        });
+        result
+    }
+}
+
+/// One piece of a path built by a derived [`PathLayout`] accessor.
+#[doc(hidden)]
+pub enum LayoutPart<'a> {
+    /// Starts a new segment, with the same rules as `PathBuf::push`.
+    Segment(&'a Path),
+    /// Appends to the current segment.
+    Append(&'a OsStr),
+}
+
+/// Joins `parts` in a stack buffer, and passes the result to `f`.
+#[doc(hidden)]
+pub fn join_layout<R>(parts: &[LayoutPart<'_>], f: impl FnOnce(&Path) -> R) -> R {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    let mut out = Writer::new(&mut raw_buff);
+    for part in parts {
+        match part {
+            LayoutPart::Segment(segment) => out.push_segment(segment.as_os_str().as_bytes(), b'/'),
+            LayoutPart::Append(bytes) => out.extend(bytes.as_bytes()),
+        }
+    }
+    f(out.finish_path(&mut path_buff))
+}
@@ -3,6 +3,123 @@
 #[cfg(test)]
 mod tests;
 
+// Lets the tests use `#[derive(PathLayout)]`, whose output names this crate
+#[cfg(test)]
+extern crate self as path_no_alloc;
+
+#[cfg(target_family = "unix")]
+mod absolute;
+mod buffer;
+#[cfg(target_family = "unix")]
+mod confined;
+#[cfg(target_os = "linux")]
+mod dir;
+#[cfg(target_family = "unix")]
+mod expand;
+#[cfg(target_family = "unix")]
+mod find_up;
+#[cfg(target_os = "linux")]
+mod glob;
+#[cfg(target_family = "unix")]
+mod ignore;
+mod keys;
+#[cfg(target_family = "unix")]
+mod layout;
+#[cfg(target_family = "unix")]
+mod path_pattern;
+#[cfg(target_family = "unix")]
+mod pattern;
+mod posix;
+#[cfg(target_os = "linux")]
+mod read_dir;
+#[cfg(target_family = "unix")]
+mod rebase;
+#[cfg(target_family = "unix")]
+mod relative;
+#[cfg(target_family = "unix")]
+mod resolve;
+#[cfg(target_family = "unix")]
+mod search;
+#[cfg(target_family = "unix")]
+mod stack_path;
+#[cfg(target_family = "unix")]
+mod sys;
+#[cfg(target_family = "unix")]
+mod template;
+#[cfg(target_family = "unix")]
+mod typed;
+mod utf8;
+#[cfg(target_os = "linux")]
+mod walk;
+#[cfg(target_os = "linux")]
+mod walk_parallel;
+#[cfg(target_family = "unix")]
+mod which;
+mod windows;
+#[cfg(target_family = "unix")]
+pub mod xdg;
+
+#[cfg(target_family = "unix")]
+pub use absolute::{absolute, absolute_from, absolute_in_buff};
+#[cfg(target_family = "unix")]
+pub use confined::{join_confined, ConfineError, Confinement};
+#[cfg(target_os = "linux")]
+pub use dir::Dir;
+#[cfg(target_family = "unix")]
+pub use expand::{expand_in_buff, expand_in_buff_with, ExpandError, ExpandOptions};
+#[cfg(target_family = "unix")]
+pub use find_up::{find_up, find_up_with, FindUp};
+#[cfg(target_os = "linux")]
+pub use glob::{glob_walk, GlobWalk};
+#[cfg(target_family = "unix")]
+pub use ignore::Ignore;
+pub use keys::{join_key_in_buff, KeyError, KeyOptions};
+#[cfg(target_family = "unix")]
+pub use layout::PathLayout;
+#[cfg(all(target_family = "unix", feature = "derive"))]
+pub use path_no_alloc_derive::PathLayout;
+#[cfg(target_family = "unix")]
+pub use path_pattern::{Captures, PathPattern, PathRouter};
+#[cfg(target_family = "unix")]
+pub use pattern::{Pattern, PatternError};
+pub use posix::{join_posix_in_buff, PosixPath};
+#[cfg(target_os = "linux")]
+pub use read_dir::{read_dir_joined, DirEntry, FileType, ReadDirJoined};
+#[cfg(target_family = "unix")]
+pub use rebase::rebase_in_buff;
+#[cfg(target_family = "unix")]
+pub use relative::relative_to_in_buff;
+#[cfg(target_family = "unix")]
+pub use resolve::{canonicalize_into, read_link_into};
+#[cfg(target_family = "unix")]
+pub use search::{search_roots, search_roots_with};
+#[cfg(target_family = "unix")]
+pub use stack_path::StackPathBuf;
+#[cfg(target_family = "unix")]
+pub use sys::FileStat;
+#[cfg(target_family = "unix")]
+pub use template::{PathTemplate, RenderError, TemplateError};
+#[cfg(target_family = "unix")]
+pub use typed::{AbsPath, DirPath, FilePath, Join, RelPath, TypedPath, TypedPathError};
+pub use utf8::join_in_buff_utf8;
+#[cfg(target_os = "linux")]
+pub use walk::{walk, walk_with, WalkEntry, WalkOptions, Walker};
+#[cfg(target_os = "linux")]
+pub use walk_parallel::walk_parallel;
+#[cfg(target_family = "unix")]
+pub use which::{which, which_all, which_all_in, which_in, WhichAll};
+pub use windows::{
+    join_windows_in_buff, normalize_windows_in_buff, WindowsComponent, WindowsPath, WindowsPrefix,
+};
+
+/// Used by `#[derive(PathLayout)]` and `with_typed_paths!`. Not public API.
+#[doc(hidden)]
+#[cfg(target_family = "unix")]
+pub mod __private {
+    pub use crate::layout::{join_layout, LayoutPart};
+    pub use crate::typed::{typed_finish, typed_join, typed_start, Typed};
+}
+
 use std::{
     ffi::OsStr,
     mem::MaybeUninit,
@@ -69,7 +186,7 @@ pub fn join_in_buff<'a, const N: usize>(
         let end_idx = pos - 1;
         raw_buff[end_idx].write(b'\0');
 
-        let result = unsafe { std::mem::transmute(&raw_buff[..end_idx]) };
+        let result: &[u8] = unsafe { std::mem::transmute(&raw_buff[..end_idx]) };
         OsStr::from_bytes(result).as_ref()
     } else {
         let path_buff = if let Some(path) = path_buff {
@@ -119,6 +236,48 @@ pub fn join_in_buff<'a, const N: usize>(
     path_buff.as_path()
 }
 
+/// The declaration and expression modes shared by the `with_*_paths!`
+/// macros. `$join` names the macro, which has to have an `@join` arm that
+/// takes the buffers and the segments, and joins them. Not public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __with_joined_paths {
+    // Declaration mode
+    {
+        $join:ident; $( $name:ident = $( $path:ident ) / + ),*
+    } => {
+        $(
+            let mut __with_paths_arr: [std::mem::MaybeUninit<u8>; 128] = unsafe { std::mem::MaybeUninit::uninit().assume_init() };
+            let mut __with_paths_buff = None;
+            let $name = $crate::$join!(@join __with_paths_arr, __with_paths_buff, $( $path ) / +);
+        )*
+    };
+
+    // Expression mode
+    {
+        $join:ident; $( $name:ident = $( $path:ident ) / + ),*
+        => $( $statements:stmt );* $(;)?
+    } => {
+        {
+            $(
+                let mut __with_paths_arr: [std::mem::MaybeUninit<u8>; 128] = unsafe { std::mem::MaybeUninit::uninit().assume_init() };
+                let mut __with_paths_buff = None;
+                let $name = $crate::$join!(@join __with_paths_arr, __with_paths_buff, $( $path ) / +);
+            )*
+
+            $( $statements )*
+        }
+    };
+
+    // A single declaration, for macros that also take declarations other
+    // than `/` joins. The tokens in brackets are passed on to `@join`.
+    { @decl $join:ident; $name:ident = [ $($tokens:tt)* ] } => {
+        let mut __with_paths_arr: [std::mem::MaybeUninit<u8>; 128] = unsafe { std::mem::MaybeUninit::uninit().assume_init() };
+        let mut __with_paths_buff = None;
+        let $name = $crate::$join!(@join __with_paths_arr, __with_paths_buff, $($tokens)*);
+    };
+}
+
 #[doc = include_str!("../docs/with_paths.md")]
 #[macro_export]
 macro_rules! with_paths {
@@ -148,4 +307,53 @@ macro_rules! with_paths {
             $( $statements )*
         }
     };
+
+    // Called by `__with_joined_paths!` for each declaration that isn't
+    // handled by the arms above
+    { @join $arr:ident, $buff:ident, relative($target:expr, $base:expr) } => {
+        $crate::relative_to_in_buff(&mut $arr, &mut $buff, $target.as_ref(), $base.as_ref())
+    };
+    { @join $arr:ident, $buff:ident, absolute($path:expr) } => {
+        $crate::absolute_in_buff(&mut $arr, &mut $buff, $path.as_ref())
+    };
+    { @join $arr:ident, $buff:ident, rebase($path:expr, $old_root:expr, $new_root:expr) } => {
+        $crate::rebase_in_buff(&mut $arr, &mut $buff, $path.as_ref(), $old_root.as_ref(), $new_root.as_ref())
+    };
+    { @join $arr:ident, $buff:ident, $( $path:ident ) / + } => {
+        $crate::join_in_buff(&mut $arr, &mut $buff, [$($path.as_ref()),+])
+    };
+
+    // Declarations that use an operator instead of `/`, such as
+    // `relative(target, base)`, are handled one at a time
+    { @decl } => {};
+    {
+        @decl $name:ident = $op:ident $args:tt $(, $($rest:tt)* )?
+    } => {
+        $crate::__with_joined_paths!(@decl with_paths; $name = [$op $args]);
+        $( $crate::with_paths!(@decl $($rest)*); )?
+    };
+    {
+        @decl $name:ident = $( $path:ident ) / + $(, $($rest:tt)* )?
+    } => {
+        $crate::__with_joined_paths!(@decl with_paths; $name = [$( $path ) / +]);
+        $( $crate::with_paths!(@decl $($rest)*); )?
+    };
+
+    // Split the declarations from the statements that follow `=>`, if any
+    { @split [$($decls:tt)*] => $($statements:tt)* } => {
+        {
+            $crate::with_paths!(@decl $($decls)*);
+            $($statements)*
+        }
+    };
+    { @split [$($decls:tt)*] $next:tt $($rest:tt)* } => {
+        $crate::with_paths!(@split [$($decls)* $next] $($rest)*)
+    };
+    { @split [$($decls:tt)*] } => {
+        $crate::with_paths!(@decl $($decls)*);
+    };
+
+    { $name:ident = $($tokens:tt)* } => {
+        $crate::with_paths!(@split [] $name = $($tokens)*)
+    };
 }
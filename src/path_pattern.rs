@@ -0,0 +1,293 @@
+use std::{ffi::OsStr, fmt, os::unix::ffi::OsStrExt, path::Path, str::FromStr};
+
+use crate::{
+    template::{PathTemplate, Piece},
+    TemplateError,
+};
+
+/// The most distinct placeholders a [`PathPattern`] can have, so that its
+/// captures fit in a fixed array.
+const MAX_CAPTURES: usize = 16;
+
+/// The inverse of a [`PathTemplate`]: matches concrete paths against a
+/// template and captures the placeholders' values.
+///
+/// The syntax is the same as [`PathTemplate`]'s. A placeholder captures a
+/// non-empty part of a single path component, except that a placeholder
+/// making up the whole first segment, like `{root}` below, can span several
+/// components. If the same placeholder appears twice, both parts of the path
+/// have to be equal. When a path could match in more than one way, earlier
+/// placeholders take as much as they can.
+///
+/// Paths are matched byte for byte, without normalizing them, and matching
+/// doesn't allocate: captures are kept as spans into the path.
+///
+/// ```rust
+/// use path_no_alloc::PathPattern;
+///
+/// let pattern = PathPattern::new("{root}/projects/{project}/builds/{build_id}.log").unwrap();
+/// let path = std::path::Path::new("/srv/data/projects/crate/builds/42.log");
+/// let captures = pattern.captures(path).unwrap();
+/// assert_eq!(captures.get("root").unwrap(), "/srv/data");
+/// assert_eq!(captures.get_str("project"), Some("crate"));
+/// assert_eq!(captures.parse::<u32>("build_id"), Some(42));
+///
+/// assert!(pattern.captures("/srv/projects/crate/builds/42.txt").is_none());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PathPattern {
+    parts: Vec<Part>,
+    // Distinct placeholder names, indexed by `Part::Capture::index`
+    names: Vec<Box<str>>,
+    // Literal bytes every matching path starts and ends with
+    prefix: Box<[u8]>,
+    suffix: Box<[u8]>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Part {
+    Literal(Box<[u8]>),
+    Separator,
+    Capture { index: usize, multi_segment: bool },
+}
+
+impl PathPattern {
+    /// Parses a pattern.
+    pub fn new(pattern: &str) -> Result<PathPattern, TemplateError> {
+        let PathTemplate { absolute, pieces } = PathTemplate::new(pattern)?;
+
+        let mut parts = Vec::new();
+        if absolute {
+            parts.push(Part::Separator);
+        }
+        let mut names: Vec<Box<str>> = Vec::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            parts.push(match piece {
+                Piece::Literal(literal) => Part::Literal(literal.as_bytes().into()),
+                Piece::Separator => Part::Separator,
+                Piece::Placeholder(name) => {
+                    let index = match names.iter().position(|n| n == name) {
+                        Some(index) => index,
+                        None if names.len() == MAX_CAPTURES => {
+                            return Err(TemplateError::TooManyPlaceholders)
+                        }
+                        None => {
+                            names.push(name.clone());
+                            names.len() - 1
+                        }
+                    };
+                    let multi_segment =
+                        i == 0 && matches!(pieces.get(1), None | Some(Piece::Separator));
+                    Part::Capture {
+                        index,
+                        multi_segment,
+                    }
+                }
+            });
+        }
+
+        let literal_bytes = |part: &Part| match part {
+            Part::Literal(literal) => Some(literal.to_vec()),
+            Part::Separator => Some(b"/".to_vec()),
+            Part::Capture { .. } => None,
+        };
+        let prefix: Vec<u8> = parts.iter().map_while(literal_bytes).flatten().collect();
+        let mut suffix: Vec<u8> = Vec::new();
+        if parts
+            .iter()
+            .any(|part| matches!(part, Part::Capture { .. }))
+        {
+            for bytes in parts.iter().rev().map_while(literal_bytes) {
+                suffix.splice(0..0, bytes);
+            }
+        }
+        Ok(PathPattern {
+            parts,
+            names,
+            prefix: prefix.into(),
+            suffix: suffix.into(),
+        })
+    }
+
+    /// The names of the pattern's placeholders, each once, in the order they
+    /// first appear.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(|name| &**name)
+    }
+
+    /// Returns true if `path` matches the pattern.
+    pub fn is_match(&self, path: impl AsRef<Path>) -> bool {
+        self.captures(path.as_ref()).is_some()
+    }
+
+    /// Matches `path` against the pattern, and returns the placeholders'
+    /// values if it matches.
+    pub fn captures<'a>(&'a self, path: &'a (impl AsRef<Path> + ?Sized)) -> Option<Captures<'a>> {
+        let input = path.as_ref().as_os_str().as_bytes();
+        if !input.starts_with(&self.prefix) || !input.ends_with(&self.suffix) {
+            return None;
+        }
+        let mut spans = [None; MAX_CAPTURES];
+        if !match_parts(&self.parts, input, 0, &mut spans) {
+            return None;
+        }
+        Some(Captures {
+            names: &self.names,
+            input,
+            spans,
+        })
+    }
+}
+
+fn match_parts(
+    parts: &[Part],
+    input: &[u8],
+    pos: usize,
+    spans: &mut [Option<(usize, usize)>; MAX_CAPTURES],
+) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return pos == input.len();
+    };
+    match *part {
+        Part::Literal(ref literal) => {
+            input[pos..].starts_with(literal)
+                && match_parts(rest, input, pos + literal.len(), spans)
+        }
+        Part::Separator => {
+            input.get(pos) == Some(&b'/') && match_parts(rest, input, pos + 1, spans)
+        }
+        Part::Capture {
+            index,
+            multi_segment,
+        } => {
+            if let Some((start, end)) = spans[index] {
+                let captured = &input[start..end];
+                return input[pos..].starts_with(captured)
+                    && match_parts(rest, input, pos + captured.len(), spans);
+            }
+            let limit = match multi_segment {
+                true => input.len(),
+                false => input[pos..]
+                    .iter()
+                    .position(|&b| b == b'/')
+                    .map_or(input.len(), |len| pos + len),
+            };
+            for end in (pos + 1..=limit).rev() {
+                spans[index] = Some((pos, end));
+                if match_parts(rest, input, end, spans) {
+                    return true;
+                }
+            }
+            spans[index] = None;
+            false
+        }
+    }
+}
+
+/// The placeholders' values from a path that matched a [`PathPattern`],
+/// borrowed from the path.
+#[derive(Clone, Copy)]
+pub struct Captures<'a> {
+    names: &'a [Box<str>],
+    input: &'a [u8],
+    spans: [Option<(usize, usize)>; MAX_CAPTURES],
+}
+
+impl<'a> Captures<'a> {
+    /// The value of the placeholder `name`, or `None` if the pattern has no
+    /// such placeholder.
+    pub fn get(&self, name: &str) -> Option<&'a OsStr> {
+        let index = self.names.iter().position(|n| &**n == name)?;
+        let (start, end) = self.spans[index]?;
+        Some(OsStr::from_bytes(&self.input[start..end]))
+    }
+
+    /// Like [`Captures::get`], but also returns `None` if the value isn't
+    /// UTF-8.
+    pub fn get_str(&self, name: &str) -> Option<&'a str> {
+        self.get(name)?.to_str()
+    }
+
+    /// Parses the value of the placeholder `name`, such as into an integer.
+    /// Returns `None` if there's no such placeholder, or the value isn't
+    /// UTF-8 or doesn't parse.
+    pub fn parse<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get_str(name)?.parse().ok()
+    }
+
+    /// The placeholders' names and values, in the order the names first
+    /// appear in the pattern.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a OsStr)> + '_ {
+        self.names.iter().zip(&self.spans).map(|(name, span)| {
+            let (start, end) = span.unwrap();
+            (&**name, OsStr::from_bytes(&self.input[start..end]))
+        })
+    }
+}
+
+impl fmt::Debug for Captures<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Matches paths against many [`PathPattern`]s, to dispatch on them.
+///
+/// Routes are tried in the order they were added, and the first match wins.
+/// Each pattern's leading and trailing literals, like the `.log` in
+/// `{root}/builds/{id}.log`, are checked before anything else, so routes
+/// that can't match are ruled out cheaply.
+///
+/// ```rust
+/// use path_no_alloc::{PathPattern, PathRouter};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Event { BuildLog, Manifest }
+///
+/// let mut router = PathRouter::new();
+/// router.add(PathPattern::new("{root}/builds/{id}.log").unwrap(), Event::BuildLog);
+/// router.add(PathPattern::new("{root}/{project}/Cargo.toml").unwrap(), Event::Manifest);
+///
+/// let (event, captures) = router.route("/srv/builds/7.log").unwrap();
+/// assert_eq!(*event, Event::BuildLog);
+/// assert_eq!(captures.parse::<u64>("id"), Some(7));
+///
+/// let (event, captures) = router.route("/srv/crate/Cargo.toml").unwrap();
+/// assert_eq!(*event, Event::Manifest);
+/// assert_eq!(captures.get_str("project"), Some("crate"));
+///
+/// assert!(router.route("/srv/README.md").is_none());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PathRouter<T> {
+    routes: Vec<(PathPattern, T)>,
+}
+
+impl<T> Default for PathRouter<T> {
+    fn default() -> Self {
+        PathRouter { routes: Vec::new() }
+    }
+}
+
+impl<T> PathRouter<T> {
+    pub fn new() -> Self {
+        PathRouter::default()
+    }
+
+    /// Adds a route, which is tried after the routes added before it.
+    pub fn add(&mut self, pattern: PathPattern, value: T) {
+        self.routes.push((pattern, value));
+    }
+
+    /// Finds the first route that `path` matches, and returns its value
+    /// along with the captures.
+    pub fn route<'a>(
+        &'a self,
+        path: &'a (impl AsRef<Path> + ?Sized),
+    ) -> Option<(&'a T, Captures<'a>)> {
+        let path = path.as_ref();
+        self.routes
+            .iter()
+            .find_map(|(pattern, value)| Some((value, pattern.captures(path)?)))
+    }
+}
@@ -0,0 +1,484 @@
+use std::{fmt, mem::MaybeUninit, os::unix::ffi::OsStrExt, path::Path};
+
+use crate::buffer::Writer;
+
+/// Brace alternation multiplies out, so cap it before it gets silly.
+const MAX_ALTERNATIVES: usize = 256;
+
+/// The reason a glob pattern couldn't be compiled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PatternError {
+    /// A `[` had no matching `]`.
+    UnclosedClass,
+    /// A `{` had no matching `}`.
+    UnclosedBrace,
+    /// The pattern ended with a `\`.
+    TrailingEscape,
+    /// Brace alternation expanded to more than 256 alternatives.
+    TooManyAlternatives,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PatternError::UnclosedClass => "`[` without a matching `]`",
+            PatternError::UnclosedBrace => "`{` without a matching `}`",
+            PatternError::TrailingEscape => "pattern ends with an unfinished `\\` escape",
+            PatternError::TooManyAlternatives => "too many brace alternatives",
+        })
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// A compiled glob pattern.
+///
+/// - `?` matches any one character except `/`.
+/// - `*` matches any run of characters except `/`.
+/// - `**` as a whole component matches any number of components, including
+///   none: `**/*.rs` matches `lib.rs` and `src/bin/main.rs`, and `target/**`
+///   matches everything inside `target`.
+/// - `[abc]`, `[a-z]` and `[!a-z]` (or `[^a-z]`) match one character that is,
+///   or isn't, in the class. They never match `/`.
+/// - `{a,b}` matches either alternative, and can be nested.
+/// - `\` matches the next character literally.
+///
+/// Matching works on the raw bytes of a path, and never allocates. A run of
+/// `/` counts as a single separator, so `src//lib.rs` matches `src/*.rs`,
+/// but nothing else is normalized: `.` and `..` have to match as written.
+///
+/// ```rust
+/// use path_no_alloc::{with_paths, Pattern};
+///
+/// let pattern = Pattern::new("src/**/*.{rs,toml}").unwrap();
+///
+/// let root = "src";
+/// let file = "bin/main.rs";
+/// with_paths! {
+///     path = root / file
+/// };
+/// assert!(pattern.matches(path));
+/// assert!(pattern.matches_segments(&["src".as_ref(), "lib.rs".as_ref()]));
+/// assert!(!pattern.matches("tests/main.rs"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    source: String,
+    alternatives: Vec<Vec<Token>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Literal(Box<[u8]>),
+    /// `?`
+    Any,
+    /// `*`
+    Star,
+    /// `**/`: any number of whole components, each with its separator.
+    GlobStarSlash,
+    /// `**` at the end: everything that's left.
+    GlobStar,
+    Class(Class),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Class {
+    negated: bool,
+    ranges: Box<[(char, char)]>,
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != self.negated
+    }
+}
+
+impl Pattern {
+    /// Compiles a glob pattern.
+    pub fn new(pattern: &str) -> Result<Pattern, PatternError> {
+        Pattern::compile(pattern, true)
+    }
+
+    pub(crate) fn compile(pattern: &str, braces: bool) -> Result<Pattern, PatternError> {
+        let mut expanded = Vec::new();
+        if braces {
+            expand_braces(pattern, &mut expanded)?;
+        } else {
+            expanded.push(pattern.to_owned());
+        }
+        let alternatives = expanded
+            .iter()
+            .map(|alt| tokenize(alt))
+            .collect::<Result<_, _>>()?;
+        Ok(Pattern {
+            source: pattern.to_owned(),
+            alternatives,
+        })
+    }
+
+    /// The pattern this was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns true if the whole of `path` matches the pattern.
+    pub fn matches(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref().as_os_str().as_bytes();
+        self.alternatives
+            .iter()
+            .any(|tokens| match_tokens(tokens, path, false))
+    }
+
+    /// Joins `segments` in a stack buffer, skipping empty ones like
+    /// `join_in_buff`, and returns true if the result matches the pattern.
+    /// The joined path can differ from `join_in_buff`'s in how many `/`
+    /// separate two segments, which doesn't change the result.
+    pub fn matches_segments(&self, segments: &[&Path]) -> bool {
+        let mut raw_buff: [MaybeUninit<u8>; 256] = [MaybeUninit::uninit(); 256];
+        let mut out = Writer::new(&mut raw_buff);
+        for segment in segments {
+            let segment = segment.as_os_str().as_bytes();
+            // `join_in_buff` skips empty segments rather than adding a `/`
+            if !segment.is_empty() {
+                out.push_segment(segment, b'/');
+            }
+        }
+        self.alternatives
+            .iter()
+            .any(|tokens| match_tokens(tokens, out.as_bytes(), false))
+    }
+
+    /// Returns true if some path inside the directory `dir` could match the
+    /// pattern. When this is false, a walk can skip `dir` entirely.
+    ///
+    /// This is conservative: it can return true for a directory that turns
+    /// out to contain no matches, but never false for one that does.
+    pub fn could_match_within(&self, dir: impl AsRef<Path>) -> bool {
+        let dir = dir.as_ref().as_os_str().as_bytes();
+        let mut raw_buff: [MaybeUninit<u8>; 256] = [MaybeUninit::uninit(); 256];
+        let mut out = Writer::new(&mut raw_buff);
+        out.extend(dir);
+        if out.len() > 0 && out.last() != Some(b'/') {
+            out.push(b'/');
+        }
+        self.alternatives
+            .iter()
+            .any(|tokens| match_tokens(tokens, out.as_bytes(), true))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Matches `input` against `tokens`. If `partial` is true, running out of
+/// input counts as a match, since more could follow.
+///
+/// This only ever backtracks to the last `*` and the last `**/`, so it runs
+/// in `O(tokens * input)` time however many wildcards there are. Going back
+/// any further never helps: a `*` can't cross a `/`, so once the match has
+/// moved past a `*`'s component, where that `*` stopped doesn't change
+/// anything after it. Likewise a later `**/` can take any components an
+/// earlier one would have.
+fn match_tokens(tokens: &[Token], input: &[u8], partial: bool) -> bool {
+    let (mut t, mut i) = (0, 0);
+    // The token after the last `*`, and where in the input that `*` stops
+    let mut star: Option<(usize, usize)> = None;
+    // The same for the last `**/`
+    let mut globstar: Option<(usize, usize)> = None;
+    loop {
+        if partial && i == input.len() {
+            return true;
+        }
+        let rest = &input[i..];
+        let matched = match tokens.get(t) {
+            None => rest.is_empty(),
+            Some(Token::Literal(literal)) => match match_literal(literal, rest) {
+                LiteralMatch::Matched(len) => {
+                    i += len;
+                    true
+                }
+                // The input ran out part way through the literal
+                LiteralMatch::RanOut if partial => return true,
+                LiteralMatch::RanOut | LiteralMatch::Mismatch => false,
+            },
+            Some(Token::Any) => match next_char(rest) {
+                Some((c, len)) if c != '/' => {
+                    i += len;
+                    true
+                }
+                _ => false,
+            },
+            Some(Token::Class(class)) => match next_char(rest) {
+                Some((c, len)) if c != '/' && class.matches(c) => {
+                    i += len;
+                    true
+                }
+                _ => false,
+            },
+            Some(Token::Star) => {
+                star = Some((t + 1, i));
+                true
+            }
+            // Whatever input is left can be the start of components it takes
+            Some(Token::GlobStarSlash) if partial => return true,
+            Some(Token::GlobStarSlash) => {
+                globstar = Some((t + 1, i));
+                star = None;
+                true
+            }
+            Some(Token::GlobStar) => return true,
+        };
+        if matched {
+            if t == tokens.len() {
+                return true;
+            }
+            t += 1;
+            continue;
+        }
+
+        // Let the last `*` take one more character, as long as it stays
+        // within its component
+        if let Some((star_t, star_i)) = star {
+            if let Some((c, len)) = next_char(&input[star_i..]) {
+                if c != '/' {
+                    star = Some((star_t, star_i + len));
+                    (t, i) = (star_t, star_i + len);
+                    continue;
+                }
+            }
+        }
+        // Otherwise let the last `**/` take one more component
+        if let Some((globstar_t, globstar_i)) = globstar {
+            if let Some(slash) = input[globstar_i..].iter().position(|&b| b == b'/') {
+                let globstar_i = skip_slashes(input, globstar_i + slash);
+                globstar = Some((globstar_t, globstar_i));
+                star = None;
+                (t, i) = (globstar_t, globstar_i);
+                continue;
+            }
+        }
+        return false;
+    }
+}
+
+enum LiteralMatch {
+    /// The literal matched this much of the input.
+    Matched(usize),
+    /// The input is a prefix of the literal.
+    RanOut,
+    Mismatch,
+}
+
+/// Matches `literal` against the start of `input`, where a run of `/` in the
+/// input matches each `/` in the literal.
+fn match_literal(literal: &[u8], input: &[u8]) -> LiteralMatch {
+    let mut i = 0;
+    for &b in literal {
+        match input.get(i) {
+            None => return LiteralMatch::RanOut,
+            Some(&c) if c != b => return LiteralMatch::Mismatch,
+            Some(_) if b == b'/' => i = skip_slashes(input, i),
+            Some(_) => i += 1,
+        }
+    }
+    LiteralMatch::Matched(i)
+}
+
+/// Returns the index just past the run of `/` that starts at `i`.
+fn skip_slashes(input: &[u8], i: usize) -> usize {
+    i + input[i..].iter().take_while(|&&b| b == b'/').count()
+}
+
+/// Decodes the character at the start of `input`. Bytes that aren't valid
+/// UTF-8 are treated as one character each.
+fn next_char(input: &[u8]) -> Option<(char, usize)> {
+    let first = *input.first()?;
+    if first < 0x80 {
+        return Some((first as char, 1));
+    }
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return Some((char::REPLACEMENT_CHARACTER, 1)),
+    };
+    match input.get(..len).map(std::str::from_utf8) {
+        Some(Ok(s)) => s.chars().next().map(|c| (c, len)),
+        _ => Some((char::REPLACEMENT_CHARACTER, 1)),
+    }
+}
+
+/// Expands the first top-level brace group in `pattern`, and recursively the
+/// rest, pushing every alternative onto `out`.
+fn expand_braces(pattern: &str, out: &mut Vec<String>) -> Result<(), PatternError> {
+    let bytes = pattern.as_bytes();
+    let mut i = 0;
+    let mut open = None;
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'[' => i = class_end(bytes, i).ok_or(PatternError::UnclosedClass)?,
+            b'{' => {
+                if depth == 0 {
+                    open = Some(i);
+                }
+                depth += 1;
+            }
+            b',' if depth == 1 => commas.push(i),
+            b'}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let open = open.unwrap();
+                    let prefix = &pattern[..open];
+                    let suffix = &pattern[i + 1..];
+                    let mut start = open + 1;
+                    for end in commas.iter().copied().chain([i]) {
+                        let alternative = format!("{prefix}{}{suffix}", &pattern[start..end]);
+                        expand_braces(&alternative, out)?;
+                        start = end + 1;
+                    }
+                    return Ok(());
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if depth > 0 {
+        return Err(PatternError::UnclosedBrace);
+    }
+    if out.len() == MAX_ALTERNATIVES {
+        return Err(PatternError::TooManyAlternatives);
+    }
+    out.push(pattern.to_owned());
+    Ok(())
+}
+
+/// Returns the index of the `]` that closes the class starting at `start`.
+fn class_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    if matches!(bytes.get(i), Some(b'!' | b'^')) {
+        i += 1;
+    }
+    // A `]` right at the start is part of the class
+    if bytes.get(i) == Some(&b']') {
+        i += 1;
+    }
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b']' => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn tokenize(pattern: &str) -> Result<Vec<Token>, PatternError> {
+    let mut tokens = Vec::new();
+    let mut literal = Vec::new();
+    let mut chars = pattern.char_indices().peekable();
+    let mut segment_start = true;
+
+    fn flush(tokens: &mut Vec<Token>, literal: &mut Vec<u8>) {
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(std::mem::take(literal).into()));
+        }
+    }
+
+    while let Some((i, c)) = chars.next() {
+        let at_segment_start = std::mem::replace(&mut segment_start, false);
+        match c {
+            '*' if at_segment_start && pattern[i..].starts_with("**/") => {
+                flush(&mut tokens, &mut literal);
+                if tokens.last() != Some(&Token::GlobStarSlash) {
+                    tokens.push(Token::GlobStarSlash);
+                }
+                chars.next();
+                chars.next();
+                segment_start = true;
+            }
+            '*' if at_segment_start && &pattern[i..] == "**" => {
+                flush(&mut tokens, &mut literal);
+                tokens.push(Token::GlobStar);
+                break;
+            }
+            '*' => {
+                flush(&mut tokens, &mut literal);
+                while chars.next_if(|&(_, c)| c == '*').is_some() {}
+                tokens.push(Token::Star);
+            }
+            '?' => {
+                flush(&mut tokens, &mut literal);
+                tokens.push(Token::Any);
+            }
+            '[' => {
+                flush(&mut tokens, &mut literal);
+                let end = class_end(pattern.as_bytes(), i).ok_or(PatternError::UnclosedClass)?;
+                tokens.push(Token::Class(parse_class(&pattern[i + 1..end])?));
+                while chars.next_if(|&(j, _)| j <= end).is_some() {}
+            }
+            '\\' => {
+                let (_, escaped) = chars.next().ok_or(PatternError::TrailingEscape)?;
+                let mut buff = [0; 4];
+                literal.extend_from_slice(escaped.encode_utf8(&mut buff).as_bytes());
+            }
+            // A run of `/` is one separator, as it is in the input
+            '/' if literal.last() == Some(&b'/')
+                || literal.is_empty() && tokens.last() == Some(&Token::GlobStarSlash) =>
+            {
+                segment_start = true;
+            }
+            c => {
+                let mut buff = [0; 4];
+                literal.extend_from_slice(c.encode_utf8(&mut buff).as_bytes());
+                segment_start = c == '/';
+            }
+        }
+    }
+    flush(&mut tokens, &mut literal);
+    Ok(tokens)
+}
+
+/// Parses the inside of a `[...]` class.
+fn parse_class(class: &str) -> Result<Class, PatternError> {
+    let (negated, class) = match class.strip_prefix(['!', '^']) {
+        Some(rest) => (true, rest),
+        None => (false, class),
+    };
+
+    let mut chars = Vec::new();
+    let mut iter = class.chars();
+    while let Some(c) = iter.next() {
+        match c {
+            '\\' => chars.push((iter.next().ok_or(PatternError::TrailingEscape)?, true)),
+            c => chars.push((c, false)),
+        }
+    }
+
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (lo, _) = chars[i];
+        match chars.get(i + 1..i + 3) {
+            Some([('-', false), (hi, _)]) => {
+                ranges.push((lo, *hi));
+                i += 3;
+            }
+            _ => {
+                ranges.push((lo, lo));
+                i += 1;
+            }
+        }
+    }
+    Ok(Class {
+        negated,
+        ranges: ranges.into(),
+    })
+}
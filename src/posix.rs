@@ -0,0 +1,157 @@
+use std::{fmt, mem::MaybeUninit};
+
+use crate::buffer::Writer;
+
+/// A `/`-separated path that follows POSIX rules regardless of the host OS.
+///
+/// This is the path type for tar entry names, object-store keys, URL paths,
+/// and anything else where `\` is just another byte and `C:` isn't special.
+/// It's a thin wrapper around `[u8]`, the same way `Path` is a thin wrapper
+/// around `OsStr`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct PosixPath([u8]);
+
+impl PosixPath {
+    /// Wraps a string or byte slice as a `PosixPath`.
+    pub fn new<S: AsRef<[u8]> + ?Sized>(s: &S) -> &PosixPath {
+        let bytes: &[u8] = s.as_ref();
+        // PosixPath is repr(transparent) over [u8]
+        unsafe { &*(bytes as *const [u8] as *const PosixPath) }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the path as a `&str`, if it's valid UTF-8.
+    pub fn to_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        self.0.first() == Some(&b'/')
+    }
+
+    /// Iterates over the components of the path, skipping empty and `.`
+    /// components. The root of an absolute path isn't included.
+    pub fn components(&self) -> impl DoubleEndedIterator<Item = &[u8]> {
+        self.0
+            .split(|b| *b == b'/')
+            .filter(|c| !c.is_empty() && *c != b".")
+    }
+
+    /// Returns the last component of the path, unless it's `..`.
+    pub fn file_name(&self) -> Option<&[u8]> {
+        self.components().next_back().filter(|c| *c != b"..")
+    }
+
+    /// Returns the path without its last component, or `None` if the path
+    /// is a root or empty.
+    pub fn parent(&self) -> Option<&PosixPath> {
+        let trimmed = trim_trailing(&self.0);
+        let last = trimmed.iter().rposition(|b| *b == b'/');
+        match last {
+            None if trimmed.is_empty() => None,
+            None => Some(PosixPath::new(b"")),
+            Some(_) if trimmed.len() == 1 => None,
+            Some(idx) => {
+                let parent = trim_trailing(&trimmed[..idx]);
+                if parent.is_empty() {
+                    Some(PosixPath::new(b"/"))
+                } else {
+                    Some(PosixPath::new(parent))
+                }
+            }
+        }
+    }
+}
+
+/// Trims trailing `/` and `/.`, but never trims a lone `/`.
+fn trim_trailing(mut bytes: &[u8]) -> &[u8] {
+    loop {
+        match bytes {
+            [rest @ .., b'/'] if !rest.is_empty() => bytes = rest,
+            [rest @ .., b'/', b'.'] => bytes = if rest.is_empty() { b"/" } else { rest },
+            [b'.'] => return b"",
+            _ => return bytes,
+        }
+    }
+}
+
+impl AsRef<[u8]> for PosixPath {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<PosixPath> for PosixPath {
+    fn as_ref(&self) -> &PosixPath {
+        self
+    }
+}
+
+impl fmt::Debug for PosixPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&String::from_utf8_lossy(&self.0), f)
+    }
+}
+
+impl fmt::Display for PosixPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&String::from_utf8_lossy(&self.0), f)
+    }
+}
+
+/// Joins N POSIX paths. If the paths fit inside the given buffer,
+/// uses the buffer. Otherwise, uses the given byte buffer.
+///
+/// Paths are joined byte-for-byte the way `PathBuf::push` joins them on unix:
+/// an absolute path replaces everything before it, and a `/` is only inserted
+/// when the previous path doesn't already end in one.
+pub fn join_posix_in_buff<'a, const N: usize>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    bytes_buff: &'a mut Option<Vec<u8>>,
+    paths: [&[u8]; N],
+) -> &'a PosixPath {
+    // Anything before the last absolute path gets discarded anyways
+    let start_idx = paths
+        .iter()
+        .rposition(|p| p.first() == Some(&b'/'))
+        .unwrap_or(0);
+    let paths = &paths[start_idx..];
+
+    let mut out = Writer::new(raw_buff);
+    out.reserve(paths.iter().map(|p| p.len() + 1).sum());
+    for path in paths {
+        out.push_segment(path, b'/');
+    }
+    PosixPath::new(out.finish_bytes(bytes_buff))
+}
+
+/// Like `with_paths!`, but joins paths with [`join_posix_in_buff`], so that
+/// every declared variable is a [`&PosixPath`](PosixPath) built with `/` as
+/// the separator on every platform. Anything that implements `AsRef<[u8]>`
+/// (including `&str` and `String`) can be joined.
+///
+/// ```rust
+/// use path_no_alloc::with_posix_paths;
+///
+/// let prefix = "backups/2022";
+/// let entry = "etc/hosts";
+///
+/// let name = with_posix_paths! {
+///     name = prefix / entry => name.to_str().unwrap().to_owned()
+/// };
+/// assert_eq!(name, "backups/2022/etc/hosts");
+/// ```
+#[macro_export]
+macro_rules! with_posix_paths {
+    // Called by `__with_joined_paths!` for each declaration
+    { @join $arr:ident, $buff:ident, $( $path:ident ) / + } => {
+        $crate::join_posix_in_buff(&mut $arr, &mut $buff, [$($path.as_ref()),+])
+    };
+    { $($tokens:tt)* } => {
+        $crate::__with_joined_paths!(with_posix_paths; $($tokens)*)
+    };
+}
@@ -0,0 +1,293 @@
+use std::{
+    ffi::{CStr, OsStr},
+    fs::OpenOptions,
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::{ffi::OsStrExt, fs::OpenOptionsExt},
+    },
+    path::Path,
+};
+
+use crate::{
+    sys::{cvt, FileStat},
+    StackPathBuf,
+};
+
+/// The type of a directory entry, as reported by the directory itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FileType {
+    File,
+    Dir,
+    Symlink,
+    Fifo,
+    Socket,
+    CharDevice,
+    BlockDevice,
+    /// The filesystem doesn't report types, so finding out needs a `stat`.
+    Unknown,
+}
+
+impl FileType {
+    fn from_d_type(d_type: u8) -> FileType {
+        match d_type {
+            libc::DT_REG => FileType::File,
+            libc::DT_DIR => FileType::Dir,
+            libc::DT_LNK => FileType::Symlink,
+            libc::DT_FIFO => FileType::Fifo,
+            libc::DT_SOCK => FileType::Socket,
+            libc::DT_CHR => FileType::CharDevice,
+            libc::DT_BLK => FileType::BlockDevice,
+            _ => FileType::Unknown,
+        }
+    }
+
+    pub(crate) fn from_stat(stat: &FileStat) -> FileType {
+        match stat.mode() & libc::S_IFMT {
+            libc::S_IFREG => FileType::File,
+            libc::S_IFDIR => FileType::Dir,
+            libc::S_IFLNK => FileType::Symlink,
+            libc::S_IFIFO => FileType::Fifo,
+            libc::S_IFSOCK => FileType::Socket,
+            libc::S_IFCHR => FileType::CharDevice,
+            libc::S_IFBLK => FileType::BlockDevice,
+            _ => FileType::Unknown,
+        }
+    }
+
+    pub fn is_dir(self) -> bool {
+        self == FileType::Dir
+    }
+
+    pub fn is_file(self) -> bool {
+        self == FileType::File
+    }
+
+    pub fn is_symlink(self) -> bool {
+        self == FileType::Symlink
+    }
+}
+
+/// Opens the directory `name` relative to `dir`. A symlink is only followed
+/// if `follow` is true.
+pub(crate) fn open_dir_at(dir: RawFd, name: &CStr, follow: bool) -> io::Result<OwnedFd> {
+    let mut flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+    if !follow {
+        flags |= libc::O_NOFOLLOW;
+    }
+    let fd = cvt(unsafe { libc::openat(dir, name.as_ptr(), flags) })?;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// One entry read by [`DirStream`], borrowing from its buffer.
+pub(crate) struct RawEntry<'a> {
+    pub(crate) name: &'a CStr,
+    pub(crate) file_type: FileType,
+    pub(crate) ino: u64,
+}
+
+/// Reads entries out of an open directory with `getdents64`, into a fixed
+/// buffer that's reused for every batch. `.` and `..` are skipped.
+pub(crate) struct DirStream {
+    fd: OwnedFd,
+    // u64s so that the entries are aligned. Boxed, so that moving a stream,
+    // such as when the walker's stack of them grows, doesn't copy 8 KiB
+    buff: Box<[u64; 1024]>,
+    pos: usize,
+    end: usize,
+}
+
+impl DirStream {
+    pub(crate) fn new(fd: OwnedFd) -> DirStream {
+        DirStream {
+            fd,
+            buff: Box::new([0; 1024]),
+            pos: 0,
+            end: 0,
+        }
+    }
+
+    /// Opens the directory `name` relative to `dir`. A symlink is only
+    /// followed if `follow` is true.
+    pub(crate) fn open_at(dir: RawFd, name: &CStr, follow: bool) -> io::Result<DirStream> {
+        open_dir_at(dir, name, follow).map(DirStream::new)
+    }
+
+    pub(crate) fn fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+
+    /// Duplicates the directory's fd, so that entries can still be opened
+    /// relative to it once the stream is gone.
+    pub(crate) fn try_clone_fd(&self) -> io::Result<OwnedFd> {
+        self.fd.try_clone()
+    }
+
+    pub(crate) fn next_raw(&mut self) -> Option<io::Result<RawEntry<'_>>> {
+        loop {
+            if self.pos >= self.end {
+                let len = unsafe {
+                    libc::syscall(
+                        libc::SYS_getdents64,
+                        self.fd.as_raw_fd(),
+                        self.buff.as_mut_ptr(),
+                        std::mem::size_of_val(&*self.buff),
+                    )
+                };
+                if len < 0 {
+                    return Some(Err(io::Error::last_os_error()));
+                }
+                if len == 0 {
+                    return None;
+                }
+                self.pos = 0;
+                self.end = len as usize;
+            }
+
+            // struct linux_dirent64 {
+            //     u64 d_ino; s64 d_off; u16 d_reclen; u8 d_type; char d_name[];
+            // }
+            let bytes =
+                unsafe { std::slice::from_raw_parts(self.buff.as_ptr() as *const u8, self.end) };
+            let entry = &bytes[self.pos..];
+            let ino = u64::from_ne_bytes(entry[0..8].try_into().unwrap());
+            let reclen = u16::from_ne_bytes(entry[16..18].try_into().unwrap()) as usize;
+            let d_type = entry[18];
+            self.pos += reclen;
+
+            let name = CStr::from_bytes_until_nul(&entry[19..reclen]).unwrap();
+            if name.to_bytes() == b"." || name.to_bytes() == b".." {
+                continue;
+            }
+            return Some(Ok(RawEntry {
+                name,
+                file_type: FileType::from_d_type(d_type),
+                ino,
+            }));
+        }
+    }
+}
+
+/// Opens the directory at `dir` for reading with [`ReadDirJoined`].
+///
+/// ```rust
+/// use path_no_alloc::read_dir_joined;
+///
+/// let mut entries = read_dir_joined("src").unwrap();
+/// let mut found = false;
+/// while let Some(entry) = entries.next() {
+///     let entry = entry.unwrap();
+///     if entry.file_name() == "lib.rs" {
+///         assert_eq!(entry.path(), std::path::Path::new("src/lib.rs"));
+///         assert!(entry.file_type().is_file());
+///         found = true;
+///     }
+/// }
+/// assert!(found);
+/// ```
+pub fn read_dir_joined(dir: impl AsRef<Path>) -> io::Result<ReadDirJoined> {
+    let dir = dir.as_ref();
+    let path = StackPathBuf::from_path(dir);
+    // Checked here so that every joined path can be used as a `CStr`
+    path.as_c_str()?;
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECTORY)
+        .open(dir)?;
+    Ok(ReadDirJoined::new(DirStream::new(file.into()), path))
+}
+
+/// A lending iterator over the entries of a directory, which yields each
+/// entry's path joined onto the directory's path.
+///
+/// Entries are read with `getdents64` into a fixed buffer, and every path is
+/// built in the same buffer by overwriting the previous entry's name, so
+/// reading a directory doesn't allocate per entry. Since each entry borrows
+/// that buffer, this isn't an `Iterator`: use `while let Some(entry) =
+/// entries.next()`. `.` and `..` aren't included.
+pub struct ReadDirJoined {
+    stream: DirStream,
+    path: StackPathBuf,
+    // Length of the directory's path, plus the separator
+    dir_len: usize,
+}
+
+impl ReadDirJoined {
+    pub(crate) fn new(stream: DirStream, mut path: StackPathBuf) -> ReadDirJoined {
+        if path.len() > 0 && path.as_bytes().last() != Some(&b'/') {
+            path.extend(b"/");
+        }
+        ReadDirJoined {
+            stream,
+            dir_len: path.len(),
+            path,
+        }
+    }
+
+    /// Reads the next entry.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<io::Result<DirEntry<'_>>> {
+        let entry = match self.stream.next_raw()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+        self.path.truncate(self.dir_len);
+        self.path.extend(entry.name.to_bytes());
+        Some(Ok(DirEntry {
+            path: &self.path,
+            name_start: self.dir_len,
+            file_type: entry.file_type,
+            ino: entry.ino,
+        }))
+    }
+}
+
+/// An entry yielded by [`ReadDirJoined`].
+#[derive(Clone, Copy)]
+pub struct DirEntry<'a> {
+    path: &'a StackPathBuf,
+    name_start: usize,
+    file_type: FileType,
+    ino: u64,
+}
+
+impl<'a> DirEntry<'a> {
+    /// The entry's path, which is the directory's path joined with
+    /// [`DirEntry::file_name`].
+    pub fn path(&self) -> &'a Path {
+        self.path.as_path()
+    }
+
+    /// The entry's path as a null-terminated string, ready to pass to the OS.
+    pub fn c_path(&self) -> &'a CStr {
+        // The directory's path was checked when it was opened, and file
+        // names never contain null bytes
+        self.path.as_c_str().unwrap()
+    }
+
+    pub fn file_name(&self) -> &'a OsStr {
+        OsStr::from_bytes(&self.path.as_bytes()[self.name_start..])
+    }
+
+    /// The entry's type, straight from the directory, without a `stat`.
+    /// Symlinks are reported as [`FileType::Symlink`], not as the type of
+    /// their target.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// The entry's inode number.
+    pub fn ino(&self) -> u64 {
+        self.ino
+    }
+}
+
+impl std::fmt::Debug for DirEntry<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirEntry")
+            .field("path", &self.path())
+            .field("file_type", &self.file_type)
+            .field("ino", &self.ino)
+            .finish()
+    }
+}
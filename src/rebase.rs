@@ -0,0 +1,34 @@
+use std::{
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf, StripPrefixError},
+};
+
+use crate::buffer::Writer;
+
+/// Moves `path` from under `old_root` to under `new_root`, i.e. computes
+/// `new_root / path.strip_prefix(old_root)`. If the result fits inside the
+/// given buffer, uses the buffer. Otherwise, uses the given pathbuff.
+///
+/// Like `Path::strip_prefix`, prefixes are matched component by component,
+/// and an error is returned when `path` isn't under `old_root`. Rebasing
+/// `old_root` itself gives back `new_root`.
+pub fn rebase_in_buff<'a>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    path_buff: &'a mut Option<PathBuf>,
+    path: &Path,
+    old_root: &Path,
+    new_root: &Path,
+) -> Result<&'a Path, StripPrefixError> {
+    let rest = path.strip_prefix(old_root)?.as_os_str().as_bytes();
+    let new_root = new_root.as_os_str().as_bytes();
+
+    let mut out = Writer::new(raw_buff);
+    out.reserve(new_root.len() + rest.len() + 1);
+    out.extend(new_root);
+    // `rest` is never absolute, so it can't discard `new_root`
+    if !rest.is_empty() {
+        out.push_segment(rest, b'/');
+    }
+    Ok(out.finish_path(path_buff))
+}
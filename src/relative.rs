@@ -0,0 +1,69 @@
+use std::{
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::buffer::Writer;
+
+/// Computes the path of `target` relative to `base`, lexically. If the result
+/// fits inside the given buffer, uses the buffer. Otherwise, uses the given
+/// pathbuff.
+///
+/// The result is built from `..` components followed by the part of `target`
+/// that isn't shared with `base`, so that `base.join(result)` refers to
+/// `target`. If the two paths are equal, the result is empty.
+///
+/// If `target` is absolute and `base` isn't, the result is just `target`.
+/// Returns `None` when there's no way to compute the answer without looking
+/// at the filesystem: when `base` is absolute and `target` isn't, or when
+/// the part of `base` that has to be walked back out of contains `..`.
+pub fn relative_to_in_buff<'a>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    path_buff: &'a mut Option<PathBuf>,
+    target: &Path,
+    base: &Path,
+) -> Option<&'a Path> {
+    let mut out = Writer::new(raw_buff);
+
+    if target.is_absolute() != base.is_absolute() {
+        if !target.is_absolute() {
+            return None;
+        }
+        out.extend(target.as_os_str().as_bytes());
+        return Some(out.finish_path(path_buff));
+    }
+
+    let mut target = target.components().filter(|c| *c != Component::CurDir);
+    let mut base = base.components().filter(|c| *c != Component::CurDir);
+    loop {
+        match (target.next(), base.next()) {
+            (None, None) => break,
+            (Some(t), None) => {
+                out.push_segment(t.as_os_str().as_bytes(), b'/');
+                break;
+            }
+            (None, Some(Component::ParentDir)) => return None,
+            (None, Some(_)) => out.push_segment(b"..", b'/'),
+            (Some(t), Some(b)) if out.len() == 0 && t == b => {}
+            (Some(t), Some(b)) => {
+                if b == Component::ParentDir {
+                    return None;
+                }
+                out.push_segment(b"..", b'/');
+                for b in base.by_ref() {
+                    if b == Component::ParentDir {
+                        return None;
+                    }
+                    out.push_segment(b"..", b'/');
+                }
+                out.push_segment(t.as_os_str().as_bytes(), b'/');
+                break;
+            }
+        }
+    }
+    for t in target {
+        out.push_segment(t.as_os_str().as_bytes(), b'/');
+    }
+    Some(out.finish_path(path_buff))
+}
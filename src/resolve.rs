@@ -0,0 +1,164 @@
+use std::{ffi::CStr, io, mem::MaybeUninit, path::Path};
+
+use crate::{absolute::current_dir_into, StackPathBuf};
+
+/// The most symlinks [`canonicalize_into`] follows before failing with
+/// `ELOOP`, the same as Linux's limit.
+const MAX_SYMLINKS: usize = 40;
+
+/// Reads the target of the symlink at `path` into `out`, replacing what was
+/// there. The target is read straight into `out`'s inline buffer, and only
+/// goes through the heap if it doesn't fit.
+///
+/// ```rust
+/// use path_no_alloc::{read_link_into, StackPathBuf};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let link = dir.path().join("link");
+/// std::os::unix::fs::symlink("target/file", &link).unwrap();
+///
+/// let mut target = StackPathBuf::<128>::new();
+/// read_link_into(&link, &mut target).unwrap();
+/// assert_eq!(target, std::path::Path::new("target/file"));
+/// ```
+pub fn read_link_into<const N: usize>(
+    path: impl AsRef<Path>,
+    out: &mut StackPathBuf<N>,
+) -> io::Result<()> {
+    let path = StackPathBuf::<128>::from_path(path);
+    out.clear();
+    read_link_c(path.as_c_str()?, out)
+}
+
+/// Appends the target of the symlink at `path` to `out`.
+fn read_link_c<const N: usize>(path: &CStr, out: &mut StackPathBuf<N>) -> io::Result<()> {
+    let mut result = Ok(());
+    let fits = unsafe {
+        out.write_inline_with(|buff| match readlink(path, buff) {
+            // A full buffer means the target might've been cut off
+            Ok(len) if len < buff.len() => Some(len),
+            Ok(_) => None,
+            Err(err) => {
+                result = Err(err);
+                None
+            }
+        })
+    };
+    result?;
+    if fits {
+        return Ok(());
+    }
+
+    let mut heap = Vec::<MaybeUninit<u8>>::new();
+    loop {
+        heap.resize((heap.len() * 2).max(N * 2).max(256), MaybeUninit::uninit());
+        let len = readlink(path, &mut heap)?;
+        if len < heap.len() {
+            let target = unsafe { std::slice::from_raw_parts(heap.as_ptr() as *const u8, len) };
+            out.extend(target);
+            return Ok(());
+        }
+    }
+}
+
+fn readlink(path: &CStr, buff: &mut [MaybeUninit<u8>]) -> io::Result<usize> {
+    let len = unsafe {
+        libc::readlink(
+            path.as_ptr(),
+            buff.as_mut_ptr() as *mut libc::c_char,
+            buff.len(),
+        )
+    };
+    if len < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(len as usize)
+    }
+}
+
+/// Resolves `path` to an absolute path with no symlinks, `.` or `..`
+/// components, like `fs::canonicalize`, and writes it into `out`, replacing
+/// what was there.
+///
+/// The path is resolved one component at a time, in fixed-size buffers that
+/// only spill onto the heap for very long paths or symlink targets. Every
+/// component has to exist, so an empty path fails with `ENOENT`. After
+/// following 40 symlinks, this gives up with `ELOOP`.
+///
+/// ```rust
+/// use path_no_alloc::{canonicalize_into, StackPathBuf};
+///
+/// let mut path = StackPathBuf::<128>::new();
+/// canonicalize_into("src/../Cargo.toml", &mut path).unwrap();
+/// assert_eq!(path, std::fs::canonicalize("Cargo.toml").unwrap());
+/// ```
+pub fn canonicalize_into<const N: usize>(
+    path: impl AsRef<Path>,
+    out: &mut StackPathBuf<N>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    out.clear();
+    if path.as_os_str().is_empty() {
+        return Err(io::Error::from_raw_os_error(libc::ENOENT));
+    }
+    if path.is_absolute() {
+        out.push("/");
+    } else {
+        // getcwd always gives a canonical path
+        current_dir_into(out)?;
+    }
+
+    // What's left to resolve, and where in it the next component starts.
+    // Following a symlink replaces the front of `rest` with its target.
+    let mut rest = StackPathBuf::<128>::from_path(path);
+    let mut pos = 0;
+    let mut target = StackPathBuf::<128>::new();
+    let mut symlinks = 0;
+
+    while pos < rest.len() {
+        let bytes = &rest.as_bytes()[pos..];
+        let len = bytes.iter().position(|&b| b == b'/').unwrap_or(bytes.len());
+        let next = pos + len + 1;
+        match &bytes[..len] {
+            b"" | b"." => {}
+            b".." => {
+                out.pop();
+            }
+            name => {
+                let parent_len = out.len();
+                if out.as_bytes().last() != Some(&b'/') {
+                    out.extend(b"/");
+                }
+                out.extend(name);
+
+                let stat = out.symlink_stat()?;
+                if stat.is_symlink() {
+                    symlinks += 1;
+                    if symlinks > MAX_SYMLINKS {
+                        return Err(io::Error::from_raw_os_error(libc::ELOOP));
+                    }
+                    target.clear();
+                    read_link_c(out.as_c_str()?, &mut target)?;
+                    if let Some(remaining) = rest.as_bytes().get(next..) {
+                        target.extend(b"/");
+                        target.extend(remaining);
+                    }
+                    std::mem::swap(&mut rest, &mut target);
+                    pos = 0;
+
+                    out.truncate(parent_len);
+                    if rest.as_bytes().first() == Some(&b'/') {
+                        out.truncate(0);
+                        out.push("/");
+                    }
+                    continue;
+                }
+                if !stat.is_dir() && pos + len < rest.len() {
+                    return Err(io::Error::from_raw_os_error(libc::ENOTDIR));
+                }
+            }
+        }
+        pos = next;
+    }
+    Ok(())
+}
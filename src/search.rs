@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use crate::{FileStat, StackPathBuf};
+
+/// Joins `relative` onto each of `roots` in turn, and returns the first
+/// joined path that exists along with the index of the root it came from.
+///
+/// Every candidate is built in the same buffer, and checked with `stat`
+/// directly on that buffer, so a search over many roots doesn't allocate
+/// unless one of the candidates is too long to fit inline. Like
+/// `Path::join`, an absolute `relative` replaces the root, so it's only
+/// ever checked against the first root.
+///
+/// ```rust
+/// use path_no_alloc::search_roots;
+///
+/// let roots = ["/path-no-alloc/missing", env!("CARGO_MANIFEST_DIR")];
+/// let (index, path) = search_roots(&roots, "Cargo.toml").unwrap();
+/// assert_eq!(index, 1);
+/// assert!(path.ends_with("Cargo.toml"));
+/// ```
+pub fn search_roots<R: AsRef<Path>>(
+    roots: &[R],
+    relative: impl AsRef<Path>,
+) -> Option<(usize, StackPathBuf)> {
+    search_roots_with(roots, relative, |_| true)
+}
+
+/// Like [`search_roots`], but a candidate only matches if it exists and
+/// `predicate` returns true for it. For example, `FileStat::is_file` skips
+/// directories, and `FileStat::is_executable` finds programs.
+pub fn search_roots_with<R: AsRef<Path>>(
+    roots: &[R],
+    relative: impl AsRef<Path>,
+    mut predicate: impl FnMut(&FileStat) -> bool,
+) -> Option<(usize, StackPathBuf)> {
+    let relative = relative.as_ref();
+    let mut path = StackPathBuf::new();
+    for (index, root) in roots.iter().enumerate() {
+        path.clear();
+        path.push(root);
+        path.push(relative);
+        if path.stat().is_ok_and(|stat| predicate(&stat)) {
+            return Some((index, path));
+        }
+        if relative.is_absolute() {
+            break;
+        }
+    }
+    None
+}
@@ -0,0 +1,332 @@
+use std::{
+    borrow::Borrow,
+    ffi::{CStr, OsStr, OsString},
+    fmt, io,
+    mem::MaybeUninit,
+    ops::Deref,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::sys::{self, FileStat};
+
+/// An owned, mutable path that's stored inline when it's shorter than `N`
+/// bytes, and on the heap otherwise.
+///
+/// This is the owned counterpart to the paths produced by `with_paths!`: it
+/// can be returned from functions and kept around, but it still doesn't
+/// allocate for short paths. It dereferences to `Path`, so all of the usual
+/// `Path` methods are available.
+///
+/// The path is always followed by a null terminator, so it can be handed to
+/// the OS (see [`StackPathBuf::stat`]) without being copied first. The
+/// terminator needs a byte of the inline buffer, so `N` has to be at least 1:
+///
+/// ```rust,compile_fail
+/// use path_no_alloc::StackPathBuf;
+///
+/// let path = StackPathBuf::<0>::new();
+/// ```
+#[derive(Clone)]
+pub struct StackPathBuf<const N: usize = 128> {
+    inline: [MaybeUninit<u8>; N],
+    len: usize,
+    // Includes the null terminator
+    heap: Option<Vec<u8>>,
+}
+
+impl<const N: usize> StackPathBuf<N> {
+    // Evaluated when `new` is instantiated, so `N == 0` fails to compile
+    const NON_EMPTY: () = assert!(N > 0, "StackPathBuf needs room for a null terminator");
+
+    /// Creates an empty path.
+    pub fn new() -> Self {
+        let () = Self::NON_EMPTY;
+        let mut path = StackPathBuf {
+            inline: [MaybeUninit::uninit(); N],
+            len: 0,
+            heap: None,
+        };
+        path.terminate();
+        path
+    }
+
+    /// Creates a `StackPathBuf` holding a copy of `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        let mut result = Self::new();
+        result.extend(path.as_ref().as_os_str().as_bytes());
+        result
+    }
+
+    pub fn as_path(&self) -> &Path {
+        OsStr::from_bytes(self.as_bytes()).as_ref()
+    }
+
+    /// Returns true if the path is stored inline, and false if it's grown
+    /// past the inline capacity and moved onto the heap.
+    pub fn is_inline(&self) -> bool {
+        self.heap.is_none()
+    }
+
+    /// Extends the path with `path`, with the same behavior as
+    /// `PathBuf::push`: if `path` is absolute, it replaces the current path.
+    pub fn push(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref().as_os_str().as_bytes();
+        if path.first() == Some(&b'/') {
+            self.truncate(0);
+        } else if self.len() > 0 && self.as_bytes().last() != Some(&b'/') {
+            self.extend(b"/");
+        }
+        self.extend(path)
+    }
+
+    /// Truncates the path to its parent, with the same behavior as
+    /// `PathBuf::pop`. Returns false if there's no parent.
+    pub fn pop(&mut self) -> bool {
+        match self.as_path().parent().map(|p| p.as_os_str().len()) {
+            Some(len) => {
+                self.truncate(len);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resolves `.` and `..` components and removes repeated separators,
+    /// without looking at the filesystem. `..` at the root stays at the root,
+    /// and leading `..` components of a relative path are kept.
+    ///
+    /// If part of the path is a symlink, the result may refer to a different
+    /// file than the original did.
+    pub fn normalize(&mut self) {
+        let original = self.clone();
+        self.clear();
+        for component in original.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => match self.components().next_back() {
+                    Some(Component::Normal(_)) => {
+                        self.pop();
+                    }
+                    Some(Component::RootDir) => {}
+                    _ => self.push(".."),
+                },
+                c => self.push(c),
+            }
+        }
+    }
+
+    /// Empties the path. If the path has moved onto the heap, it stays
+    /// there, so the allocation can be reused.
+    pub fn clear(&mut self) {
+        self.truncate(0)
+    }
+
+    pub fn into_path_buf(self) -> PathBuf {
+        self.as_path().to_path_buf()
+    }
+
+    /// Calls `stat` on the path, following symlinks, without copying the path
+    /// or allocating.
+    pub fn stat(&self) -> io::Result<FileStat> {
+        sys::stat(self.as_c_str()?, true)
+    }
+
+    /// Calls `lstat` on the path, so that a symlink is reported as a symlink.
+    pub fn symlink_stat(&self) -> io::Result<FileStat> {
+        sys::stat(self.as_c_str()?, false)
+    }
+
+    /// Returns true if the path points at an existing file or directory.
+    /// Like `Path::exists`, broken symlinks don't exist.
+    pub fn exists(&self) -> bool {
+        self.stat().is_ok()
+    }
+
+    /// Returns the path as a null-terminated string. Fails with
+    /// `InvalidInput` if the path contains a null byte.
+    pub(crate) fn as_c_str(&self) -> io::Result<&CStr> {
+        let bytes = match &self.heap {
+            Some(heap) => heap.as_slice(),
+            None => unsafe {
+                std::slice::from_raw_parts(self.inline.as_ptr() as *const u8, self.len + 1)
+            },
+        };
+        CStr::from_bytes_with_nul(bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        match &self.heap {
+            Some(heap) => &heap[..heap.len() - 1],
+            None => unsafe {
+                std::slice::from_raw_parts(self.inline.as_ptr() as *const u8, self.len)
+            },
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    /// Truncates the path to `len` bytes. Callers are responsible for cutting
+    /// at a sensible place, such as the length of one of the path's
+    /// ancestors.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        match &mut self.heap {
+            Some(heap) => {
+                if len < heap.len() - 1 {
+                    heap.truncate(len);
+                    heap.push(b'\0');
+                }
+            }
+            None => {
+                self.len = self.len.min(len);
+                self.terminate();
+            }
+        }
+    }
+
+    /// Appends raw bytes to the path, moving it onto the heap if it doesn't
+    /// fit inline anymore.
+    pub(crate) fn extend(&mut self, bytes: &[u8]) {
+        match &mut self.heap {
+            Some(heap) => {
+                heap.pop();
+                heap.extend_from_slice(bytes);
+                heap.push(b'\0');
+            }
+            None if self.len + bytes.len() < N => {
+                unsafe {
+                    core::ptr::copy_nonoverlapping(
+                        bytes.as_ptr(),
+                        self.inline[self.len..].as_mut_ptr() as *mut u8,
+                        bytes.len(),
+                    );
+                }
+                self.len += bytes.len();
+                self.terminate();
+            }
+            None => {
+                let mut heap = Vec::with_capacity((self.len + bytes.len() + 1).max(N * 2));
+                heap.extend_from_slice(self.as_bytes());
+                heap.extend_from_slice(bytes);
+                heap.push(b'\0');
+                self.heap = Some(heap);
+            }
+        }
+    }
+
+    /// Lets `f` write straight into the unused part of the inline buffer. `f`
+    /// returns how many bytes it wrote, or `None` if they didn't fit. Returns
+    /// false without calling `f` if the path has moved onto the heap.
+    ///
+    /// # Safety
+    ///
+    /// `f` must have initialized as many bytes as it says it wrote.
+    pub(crate) unsafe fn write_inline_with(
+        &mut self,
+        f: impl FnOnce(&mut [MaybeUninit<u8>]) -> Option<usize>,
+    ) -> bool {
+        if self.heap.is_some() || self.len + 1 >= N {
+            return false;
+        }
+        // Leave room for the null terminator
+        match f(&mut self.inline[self.len..N - 1]) {
+            Some(len) => {
+                self.len += len;
+                self.terminate();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn terminate(&mut self) {
+        if let Some(end) = self.inline.get_mut(self.len) {
+            end.write(b'\0');
+        }
+    }
+}
+
+impl<const N: usize> Default for StackPathBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deref for StackPathBuf<N> {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl<const N: usize> AsRef<Path> for StackPathBuf<N> {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl<const N: usize> AsRef<OsStr> for StackPathBuf<N> {
+    fn as_ref(&self) -> &OsStr {
+        self.as_path().as_os_str()
+    }
+}
+
+impl<const N: usize> Borrow<Path> for StackPathBuf<N> {
+    fn borrow(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl<const N: usize> fmt::Debug for StackPathBuf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_path(), f)
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<StackPathBuf<M>> for StackPathBuf<N> {
+    fn eq(&self, other: &StackPathBuf<M>) -> bool {
+        self.as_path() == other.as_path()
+    }
+}
+
+impl<const N: usize> Eq for StackPathBuf<N> {}
+
+impl<const N: usize> PartialEq<Path> for StackPathBuf<N> {
+    fn eq(&self, other: &Path) -> bool {
+        self.as_path() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&Path> for StackPathBuf<N> {
+    fn eq(&self, other: &&Path) -> bool {
+        self.as_path() == *other
+    }
+}
+
+impl<const N: usize> PartialEq<PathBuf> for StackPathBuf<N> {
+    fn eq(&self, other: &PathBuf) -> bool {
+        self.as_path() == other
+    }
+}
+
+impl<const N: usize> From<&Path> for StackPathBuf<N> {
+    fn from(path: &Path) -> Self {
+        Self::from_path(path)
+    }
+}
+
+impl<const N: usize> From<StackPathBuf<N>> for PathBuf {
+    fn from(path: StackPathBuf<N>) -> PathBuf {
+        match path.heap {
+            Some(mut heap) => {
+                heap.pop();
+                OsString::from_vec(heap).into()
+            }
+            None => path.as_path().to_path_buf(),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::{ffi::CStr, io, mem::MaybeUninit, os::fd::RawFd};
+
+/// Turns the return value of a libc call into an `io::Result`.
+pub(crate) fn cvt(result: libc::c_int) -> io::Result<libc::c_int> {
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+/// The parts of `struct stat` this crate cares about. Unlike
+/// `std::fs::Metadata`, it can be filled from a null-terminated path that
+/// already lives in a stack buffer, without copying the path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FileStat {
+    mode: u32,
+    len: u64,
+    dev: u64,
+    ino: u64,
+}
+
+// `mode_t` and friends aren't the same width on every platform
+#[allow(clippy::unnecessary_cast)]
+impl FileStat {
+    fn from_raw(stat: &libc::stat) -> FileStat {
+        FileStat {
+            mode: stat.st_mode as u32,
+            len: stat.st_size as u64,
+            dev: stat.st_dev as u64,
+            ino: stat.st_ino as u64,
+        }
+    }
+
+    /// The raw `st_mode`, including both the file type and the permissions.
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The ID of the device containing the file.
+    pub fn dev(&self) -> u64 {
+        self.dev
+    }
+
+    pub fn ino(&self) -> u64 {
+        self.ino
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.mode & libc::S_IFMT as u32 == libc::S_IFDIR as u32
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & libc::S_IFMT as u32 == libc::S_IFREG as u32
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & libc::S_IFMT as u32 == libc::S_IFLNK as u32
+    }
+
+    /// Returns true for regular files with at least one execute bit set.
+    pub fn is_executable(&self) -> bool {
+        self.is_file() && self.mode & 0o111 != 0
+    }
+}
+
+/// Calls `stat`, or `lstat` if `follow` is false.
+pub(crate) fn stat(path: &CStr, follow: bool) -> io::Result<FileStat> {
+    stat_at(libc::AT_FDCWD, path, follow)
+}
+
+/// Calls `fstatat` on a path relative to `dir`.
+pub(crate) fn stat_at(dir: RawFd, path: &CStr, follow: bool) -> io::Result<FileStat> {
+    let flags = if follow { 0 } else { libc::AT_SYMLINK_NOFOLLOW };
+    let mut stat = MaybeUninit::<libc::stat>::uninit();
+    cvt(unsafe { libc::fstatat(dir, path.as_ptr(), stat.as_mut_ptr(), flags) })?;
+    Ok(FileStat::from_raw(unsafe { stat.assume_init_ref() }))
+}
+
+/// Calls `fstat` on an open file.
+pub(crate) fn fstat(fd: RawFd) -> io::Result<FileStat> {
+    let mut stat = MaybeUninit::<libc::stat>::uninit();
+    cvt(unsafe { libc::fstat(fd, stat.as_mut_ptr()) })?;
+    Ok(FileStat::from_raw(unsafe { stat.assume_init_ref() }))
+}
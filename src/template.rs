@@ -0,0 +1,237 @@
+use std::{
+    fmt,
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{buffer::Writer, StackPathBuf};
+
+/// The reason a path template couldn't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TemplateError {
+    /// A `{` had no matching `}`. Use `{{` for a literal `{`.
+    UnclosedPlaceholder,
+    /// A `}` wasn't closing a placeholder. Use `}}` for a literal `}`.
+    UnmatchedBrace,
+    /// A placeholder's name was empty, or wasn't made of ASCII letters,
+    /// digits and `_`, or started with a digit.
+    InvalidName(String),
+    /// Two placeholders followed each other with nothing in between, so
+    /// there'd be no telling where one value ends and the next begins.
+    AdjacentPlaceholders(String, String),
+    /// The template had `//`, or ended with `/`.
+    EmptySegment,
+    /// A [`PathPattern`](crate::PathPattern) had more than 16 distinct
+    /// placeholders.
+    TooManyPlaceholders,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnclosedPlaceholder => f.write_str("`{` without a matching `}`"),
+            TemplateError::UnmatchedBrace => f.write_str("`}` without a matching `{`"),
+            TemplateError::InvalidName(name) => write!(f, "invalid placeholder name `{name}`"),
+            TemplateError::AdjacentPlaceholders(first, second) => {
+                write!(f, "placeholders `{first}` and `{second}` are adjacent")
+            }
+            TemplateError::EmptySegment => f.write_str("template has an empty segment"),
+            TemplateError::TooManyPlaceholders => f.write_str("too many placeholders"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// The reason a path template couldn't be rendered.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RenderError {
+    /// The template has a placeholder with no value.
+    Missing(String),
+    /// A value was given for a placeholder the template doesn't have.
+    Unused(String),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Missing(name) => write!(f, "no value for placeholder `{name}`"),
+            RenderError::Unused(name) => write!(f, "template has no placeholder `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// A path layout with named placeholders, like
+/// `"{root}/projects/{project}/builds/{build_id}.log"`, parsed once and
+/// rendered many times.
+///
+/// Placeholder names are made of ASCII letters, digits and `_`, and don't
+/// start with a digit. `{{` and `}}` stand for literal braces.
+///
+/// Rendering joins the template's `/`-separated segments the same way
+/// `join_in_buff` joins paths: a segment whose value is an absolute path
+/// replaces everything before it.
+///
+/// ```rust
+/// use std::path::Path;
+/// use path_no_alloc::PathTemplate;
+///
+/// let template = PathTemplate::new("{root}/projects/{project}/builds/{build_id}.log").unwrap();
+/// let path = template
+///     .render(&[
+///         ("root", Path::new("/srv")),
+///         ("project", Path::new("crate")),
+///         ("build_id", Path::new("42")),
+///     ])
+///     .unwrap();
+/// assert_eq!(path, Path::new("/srv/projects/crate/builds/42.log"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PathTemplate {
+    pub(crate) absolute: bool,
+    pub(crate) pieces: Vec<Piece>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Piece {
+    Literal(Box<str>),
+    Placeholder(Box<str>),
+    Separator,
+}
+
+impl PathTemplate {
+    /// Parses a template.
+    pub fn new(template: &str) -> Result<PathTemplate, TemplateError> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let absolute = template.starts_with('/');
+        let template = template.strip_prefix('/').unwrap_or(template);
+
+        fn flush(pieces: &mut Vec<Piece>, literal: &mut String) {
+            if !literal.is_empty() {
+                pieces.push(Piece::Literal(std::mem::take(literal).into()));
+            }
+        }
+
+        let mut chars = template.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|&(_, c)| c == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|&(_, c)| c == '}').is_some() => literal.push('}'),
+                '}' => return Err(TemplateError::UnmatchedBrace),
+                '{' => {
+                    let len = template[i + 1..]
+                        .find('}')
+                        .ok_or(TemplateError::UnclosedPlaceholder)?;
+                    let name = &template[i + 1..i + 1 + len];
+                    if !is_valid_name(name) {
+                        return Err(TemplateError::InvalidName(name.to_owned()));
+                    }
+                    if literal.is_empty() {
+                        if let Some(Piece::Placeholder(previous)) = pieces.last() {
+                            return Err(TemplateError::AdjacentPlaceholders(
+                                previous.to_string(),
+                                name.to_owned(),
+                            ));
+                        }
+                    }
+                    flush(&mut pieces, &mut literal);
+                    pieces.push(Piece::Placeholder(name.into()));
+                    while chars.next_if(|&(j, _)| j <= i + 1 + len).is_some() {}
+                }
+                '/' => {
+                    flush(&mut pieces, &mut literal);
+                    if matches!(pieces.last(), None | Some(Piece::Separator)) {
+                        return Err(TemplateError::EmptySegment);
+                    }
+                    pieces.push(Piece::Separator);
+                }
+                c => literal.push(c),
+            }
+        }
+        flush(&mut pieces, &mut literal);
+        if pieces.last() == Some(&Piece::Separator) {
+            return Err(TemplateError::EmptySegment);
+        }
+        Ok(PathTemplate { absolute, pieces })
+    }
+
+    /// The names of the template's placeholders, in order. A name that's
+    /// used more than once is repeated.
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.pieces.iter().filter_map(|piece| match piece {
+            Piece::Placeholder(name) => Some(&**name),
+            _ => None,
+        })
+    }
+
+    /// Renders the template with `values`, which map placeholder names to
+    /// values. If the result fits inside the given buffer, uses the buffer.
+    /// Otherwise, uses the given pathbuff.
+    ///
+    /// Every placeholder needs a value, and every value needs a placeholder.
+    pub fn render_in_buff<'a>(
+        &self,
+        raw_buff: &'a mut [MaybeUninit<u8>],
+        path_buff: &'a mut Option<PathBuf>,
+        values: &[(&str, &Path)],
+    ) -> Result<&'a Path, RenderError> {
+        for name in self.placeholders() {
+            if !values.iter().any(|&(key, _)| key == name) {
+                return Err(RenderError::Missing(name.to_owned()));
+            }
+        }
+        for &(key, _) in values {
+            if !self.placeholders().any(|name| name == key) {
+                return Err(RenderError::Unused(key.to_owned()));
+            }
+        }
+
+        let mut out = Writer::new(raw_buff);
+        if self.absolute {
+            out.push(b'/');
+        }
+        let mut segment_start = out.len();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Literal(literal) => out.extend(literal.as_bytes()),
+                Piece::Placeholder(name) => {
+                    let (_, value) = values.iter().find(|&&(key, _)| key == &**name).unwrap();
+                    let value = value.as_os_str().as_bytes();
+                    // An absolute value at the start of a segment replaces
+                    // everything before it
+                    if out.len() == segment_start && value.first() == Some(&b'/') {
+                        out.truncate(0);
+                        segment_start = 0;
+                    }
+                    out.extend(value);
+                }
+                Piece::Separator => {
+                    if out.len() > 0 && out.last() != Some(b'/') {
+                        out.push(b'/');
+                    }
+                    segment_start = out.len();
+                }
+            }
+        }
+        Ok(out.finish_path(path_buff))
+    }
+
+    /// Like [`PathTemplate::render_in_buff`], but renders into a
+    /// [`StackPathBuf`].
+    pub fn render(&self, values: &[(&str, &Path)]) -> Result<StackPathBuf, RenderError> {
+        let mut raw_buff = [MaybeUninit::uninit(); 128];
+        let mut path_buff = None;
+        self.render_in_buff(&mut raw_buff, &mut path_buff, values)
+            .map(StackPathBuf::from_path)
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    matches!(bytes.next(), Some(b'a'..=b'z' | b'A'..=b'Z' | b'_'))
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
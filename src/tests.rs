@@ -1,3 +1,52 @@
+#[cfg(target_family = "unix")]
+mod absolute;
+#[cfg(target_family = "unix")]
+mod confined;
+#[cfg(target_os = "linux")]
+mod dir;
+#[cfg(target_family = "unix")]
+mod expand;
+#[cfg(target_family = "unix")]
+mod find_up;
+#[cfg(target_os = "linux")]
+mod glob;
+#[cfg(target_family = "unix")]
+mod ignore;
+mod keys;
+#[cfg(target_family = "unix")]
+mod layout;
+#[cfg(target_family = "unix")]
+mod path_pattern;
+#[cfg(target_family = "unix")]
+mod pattern;
+mod posix;
+#[cfg(target_os = "linux")]
+mod read_dir;
+#[cfg(target_family = "unix")]
+mod rebase;
+#[cfg(target_family = "unix")]
+mod relative;
+#[cfg(target_family = "unix")]
+mod resolve;
+#[cfg(target_family = "unix")]
+mod search;
+#[cfg(target_family = "unix")]
+mod stack_path;
+#[cfg(target_family = "unix")]
+mod template;
+#[cfg(target_family = "unix")]
+mod typed;
+mod utf8;
+#[cfg(target_os = "linux")]
+mod walk;
+#[cfg(target_os = "linux")]
+mod walk_parallel;
+#[cfg(target_family = "unix")]
+mod which;
+mod windows;
+#[cfg(target_family = "unix")]
+mod xdg;
+
 use std::path::{Path, PathBuf};
 
 use rand::{distributions::Uniform, prelude::Distribution};
@@ -53,6 +102,7 @@ fn test_with_paths_abs_3() {
 }
 
 #[test]
+#[allow(clippy::join_absolute_paths)]
 fn test_with_paths_abs_4() {
     let p1 = "hello//";
     let p2 = "/world/";
@@ -90,8 +140,7 @@ fn test_fuzz() {
     let length_dist = Uniform::from(0..24);
     let opt_dist = Uniform::from(0..options.len());
 
-    let mut paths: Vec<String> = vec![];
-    paths.reserve(SAMPLE_SIZE);
+    let mut paths: Vec<String> = Vec::with_capacity(SAMPLE_SIZE);
 
     for _ in 0..SAMPLE_SIZE {
         let len = length_dist.sample(&mut rng);
@@ -414,5 +463,4 @@ fn check_paths_declare(p1: impl AsRef<Path>, p2: impl AsRef<Path>, p3: impl AsRe
 
     with_paths! { path = p3 / p3 / p3 };
     assert_eq!(path, p3.as_ref().join(p3.as_ref()).join(p3.as_ref()));
-
 }
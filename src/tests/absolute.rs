@@ -0,0 +1,99 @@
+use std::{
+    env,
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{absolute, absolute_from, absolute_in_buff, with_paths, StackPathBuf};
+
+#[test]
+fn test_absolute() {
+    let cases = [
+        "a/b",
+        "../a",
+        ".",
+        "./",
+        "./a/./b//c/",
+        "a/../b/..",
+        "/x/./y",
+        "/x//y/",
+        "/",
+        "//",
+        "//x/y",
+        "///x",
+    ];
+    for path in cases {
+        // Compared as bytes, since `Path` equality ignores `.` and `//`
+        let expected = std::path::absolute(path).unwrap();
+        assert_eq!(
+            absolute(path).unwrap().as_bytes(),
+            expected.as_os_str().as_bytes(),
+            "{path}"
+        );
+    }
+    assert_eq!(
+        absolute("").unwrap_err().kind(),
+        std::path::absolute("").unwrap_err().kind()
+    );
+}
+
+#[test]
+fn test_absolute_small_buffer() {
+    // A cwd that doesn't fit in the stack buffer is read into the heap
+    let cwd = env::current_dir().unwrap();
+    for size in [0, 1, 4, cwd.as_os_str().len(), cwd.as_os_str().len() + 1] {
+        let mut raw_buff = vec![MaybeUninit::uninit(); size];
+        let mut path_buff = None;
+        let path = absolute_in_buff(&mut raw_buff, &mut path_buff, "./file".as_ref()).unwrap();
+        let expected = cwd.join("file");
+        assert_eq!(
+            path.as_os_str().as_bytes(),
+            expected.as_os_str().as_bytes(),
+            "buffer of {size}"
+        );
+    }
+}
+
+#[test]
+fn test_absolute_from() {
+    assert_eq!(absolute_from("/base", "a/b"), Path::new("/base/a/b"));
+    assert_eq!(absolute_from("/base", "/a"), Path::new("/a"));
+    assert_eq!(absolute_from("base/", "a"), Path::new("base/a"));
+}
+
+#[test]
+fn test_normalize() {
+    let cases = [
+        ("/a/b/../c", "/a/c"),
+        ("/a/./b//c/", "/a/b/c"),
+        ("/../a", "/a"),
+        ("/a/../..", "/"),
+        ("a/../..", ".."),
+        ("../../a/../b", "../../b"),
+        ("./a/.", "a"),
+        ("a/..", ""),
+        ("", ""),
+    ];
+    for (path, expected) in cases {
+        let mut normalized = StackPathBuf::<128>::from_path(path);
+        normalized.normalize();
+        assert_eq!(normalized, PathBuf::from(expected), "{path}");
+    }
+
+    let mut path = absolute_from("/srv/app", "../releases/./42/");
+    path.normalize();
+    assert_eq!(path, Path::new("/srv/releases/42"));
+}
+
+#[test]
+fn test_absolute_operator() {
+    let file = "file";
+    let root = "/root";
+    with_paths! {
+        full = absolute(file),
+        already = absolute(root)
+    };
+    assert_eq!(full.unwrap(), env::current_dir().unwrap().join(file));
+    assert_eq!(already.unwrap(), Path::new(root));
+}
@@ -0,0 +1,176 @@
+use std::{
+    mem::MaybeUninit,
+    path::{Component, Path, PathBuf},
+};
+
+use rand::{distributions::Uniform, prelude::Distribution};
+
+use crate::{join_confined, with_confined_paths, ConfineError, Confinement};
+
+fn confined<const N: usize>(
+    confinement: Confinement,
+    paths: [&str; N],
+) -> Result<PathBuf, ConfineError> {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    join_confined(
+        &mut raw_buff,
+        &mut path_buff,
+        confinement,
+        paths.map(Path::new),
+    )
+    .map(Path::to_path_buf)
+}
+
+#[test]
+fn test_confined_relative() {
+    let root = "/srv/root";
+    let p1 = "a/b";
+    let p2 = "./c/";
+    let result = with_confined_paths! { path = root / p1 / p2 => path.map(Path::to_path_buf) };
+    assert_eq!(result, Ok(PathBuf::from("/srv/root/a/b/c")));
+}
+
+#[test]
+fn test_confined_parent_within_root() {
+    assert_eq!(
+        confined(Confinement::Strict, ["/srv/root", "a/b/../c", ".."]),
+        Ok(PathBuf::from("/srv/root/a"))
+    );
+    assert_eq!(
+        confined(Confinement::Strict, ["/srv/root/", "a", ".."]),
+        Ok(PathBuf::from("/srv/root/"))
+    );
+}
+
+#[test]
+fn test_confined_escape() {
+    assert_eq!(
+        confined(Confinement::Strict, ["/srv/root", "a/../../etc"]),
+        Err(ConfineError::Escape)
+    );
+    assert_eq!(
+        confined(Confinement::Clamp, ["/srv/root", "a/../../etc"]),
+        Ok(PathBuf::from("/srv/root/etc"))
+    );
+}
+
+#[test]
+fn test_confined_absolute() {
+    assert_eq!(
+        confined(Confinement::Strict, ["/srv/root", "a", "/etc/passwd"]),
+        Err(ConfineError::Absolute)
+    );
+    assert_eq!(
+        confined(Confinement::Clamp, ["/srv/root", "a", "/etc/passwd"]),
+        Ok(PathBuf::from("/srv/root/a/etc/passwd"))
+    );
+}
+
+#[test]
+fn test_confined_nul() {
+    assert_eq!(
+        confined(Confinement::Clamp, ["/srv/root", "a\0b"]),
+        Err(ConfineError::Nul)
+    );
+    assert_eq!(
+        confined(Confinement::Clamp, ["/srv/\0root", "a"]),
+        Err(ConfineError::Nul)
+    );
+}
+
+#[test]
+fn test_confined_relative_root() {
+    assert_eq!(
+        confined(Confinement::Strict, ["", "a/./b", ".."]),
+        Ok(PathBuf::from("a"))
+    );
+    assert_eq!(
+        confined(Confinement::Strict, ["", "a", "../.."]),
+        Err(ConfineError::Escape)
+    );
+}
+
+#[test]
+fn test_confined_overflow() {
+    let root = "/some/root/path/for/good/measure/with/lots/of/slashes";
+    let p1 = "Call me Ishmael. Some years ago—never mind how long precisely—having little or no money in my purse";
+    let p2 = "../and nothing particular to interest me on shore";
+    assert!(root.len() + p1.len() + p2.len() > 128);
+
+    with_confined_paths! {
+        path = root / p1 / p2
+    };
+    assert_eq!(path, Ok(Path::new(root).join(&p2[3..]).as_path()));
+}
+
+/// Lexically resolves `paths[1..]` under `paths[0]` with a stack of
+/// components, clamping at the root.
+fn reference_clamp(paths: &[&str]) -> PathBuf {
+    let mut components = vec![];
+    for path in &paths[1..] {
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(c) => components.push(c),
+                Component::ParentDir => {
+                    components.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut result = PathBuf::from(paths[0]);
+    result.extend(components);
+    result
+}
+
+#[test]
+fn test_confined_fuzz() {
+    let mut rng = rand::thread_rng();
+    const SAMPLES: usize = 100_000;
+
+    let options: [&str; 6] = ["a", "bc", ".", "..", "/", "/.."];
+    let roots = ["", "/", "root", "/srv/root", "/srv/root/"];
+
+    let length_dist = Uniform::from(0..8);
+    let opt_dist = Uniform::from(0..options.len());
+    let root_dist = Uniform::from(0..roots.len());
+
+    let make_segment = |rng: &mut rand::rngs::ThreadRng| -> String {
+        let len = length_dist.sample(rng);
+        opt_dist
+            .sample_iter(rng)
+            .take(len)
+            .map(|i| options[i])
+            .collect()
+    };
+
+    for _ in 0..SAMPLES {
+        let root = roots[root_dist.sample(&mut rng)];
+        let p1 = make_segment(&mut rng);
+        let p2 = make_segment(&mut rng);
+        let p3 = make_segment(&mut rng);
+        let paths = [root, p1.as_str(), p2.as_str(), p3.as_str()];
+
+        let expected = reference_clamp(&paths);
+        let clamped = confined(Confinement::Clamp, paths).unwrap();
+        assert_eq!(clamped, expected, "{paths:?}");
+
+        // Whatever happens, the result stays inside the root
+        assert!(clamped.starts_with(root), "{paths:?}");
+        let rest = clamped.strip_prefix(root).unwrap();
+        assert!(
+            rest.components().all(|c| matches!(c, Component::Normal(_))),
+            "{paths:?}"
+        );
+
+        match confined(Confinement::Strict, paths) {
+            Ok(strict) => assert_eq!(strict, clamped, "{paths:?}"),
+            Err(ConfineError::Absolute) => {
+                assert!(paths[1..].iter().any(|p| p.starts_with('/')), "{paths:?}")
+            }
+            Err(ConfineError::Escape) => {}
+            Err(ConfineError::Nul) => panic!("unexpected Nul for {paths:?}"),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    fs,
+    io::{self, Read},
+    os::unix::fs::symlink,
+    path::Path,
+};
+
+use tempfile::TempDir;
+
+use crate::Dir;
+
+/// Creates a directory containing regular files alongside hostile symlinks:
+///
+/// ```text
+/// root/
+///   inside.txt
+///   sub/nested.txt
+///   sub/up -> ..
+///   escape -> /etc
+///   escape_rel -> ../..
+///   inner -> inside.txt
+/// ```
+fn hostile_dir() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join("inside.txt"), "inside").unwrap();
+    fs::create_dir(root.join("sub")).unwrap();
+    fs::write(root.join("sub/nested.txt"), "nested").unwrap();
+    symlink("..", root.join("sub/up")).unwrap();
+    symlink("/etc", root.join("escape")).unwrap();
+    symlink("../..", root.join("escape_rel")).unwrap();
+    symlink("inside.txt", root.join("inner")).unwrap();
+    dir
+}
+
+fn read_beneath(dir: &Dir, segments: &[&str], use_openat2: bool) -> io::Result<String> {
+    let segments: Vec<&Path> = segments.iter().map(Path::new).collect();
+    let fd = dir.open_beneath_with(&segments, libc::O_RDONLY, use_openat2)?;
+    let mut contents = String::new();
+    fs::File::from(fd).read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+fn check_beneath(use_openat2: bool) {
+    let tmp = hostile_dir();
+    let dir = Dir::open(tmp.path()).unwrap();
+
+    assert_eq!(
+        read_beneath(&dir, &["inside.txt"], use_openat2).unwrap(),
+        "inside"
+    );
+    assert_eq!(
+        read_beneath(&dir, &["sub", "nested.txt"], use_openat2).unwrap(),
+        "nested"
+    );
+    assert_eq!(
+        read_beneath(&dir, &["sub/../sub/./nested.txt"], use_openat2).unwrap(),
+        "nested"
+    );
+
+    // Lexical escapes are rejected before we ever touch the filesystem
+    for segments in [
+        &["../inside.txt"][..],
+        &["/etc/passwd"],
+        &["sub", "../../x"],
+    ] {
+        let err = read_beneath(&dir, segments, use_openat2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{segments:?}");
+    }
+
+    // Symlinks are never followed, whether they point inside or outside
+    for segments in [
+        &["escape", "passwd"][..],
+        &["escape_rel", "etc", "passwd"],
+        &["sub", "up", "inside.txt"],
+        &["inner"],
+    ] {
+        assert!(
+            read_beneath(&dir, segments, use_openat2).is_err(),
+            "{segments:?}"
+        );
+    }
+}
+
+#[test]
+fn test_open_beneath_openat2() {
+    check_beneath(true);
+}
+
+#[test]
+fn test_open_beneath_walk() {
+    check_beneath(false);
+}
+
+#[test]
+fn test_open_dir_beneath() {
+    let tmp = hostile_dir();
+    let dir = Dir::open(tmp.path()).unwrap();
+
+    let sub = dir.open_dir_beneath([Path::new("sub")]).unwrap();
+    let mut contents = String::new();
+    sub.open_beneath([Path::new("nested.txt")])
+        .unwrap()
+        .read_to_string(&mut contents)
+        .unwrap();
+    assert_eq!(contents, "nested");
+
+    assert!(dir.open_dir_beneath([Path::new("escape")]).is_err());
+    assert!(dir.open_dir_beneath([Path::new("inside.txt")]).is_err());
+}
@@ -0,0 +1,172 @@
+use std::{
+    ffi::{OsStr, OsString},
+    mem::MaybeUninit,
+    path::{Path, PathBuf},
+};
+
+use crate::{expand_in_buff, expand_in_buff_with, with_expanded_paths, ExpandError, ExpandOptions};
+
+fn lookup(name: &OsStr) -> Option<OsString> {
+    let value = match name.to_str()? {
+        "HOME" => "/home/me",
+        "PROJECT" => "demo",
+        "EMPTY" => "",
+        "ROOT" => "/srv",
+        "LONG" => "a-rather-long-directory-name-that-takes-up-a-good-part-of-the-buffer",
+        _ => return None,
+    };
+    Some(value.into())
+}
+
+fn expand<const N: usize>(
+    paths: [&str; N],
+    options: &ExpandOptions,
+) -> Result<PathBuf, ExpandError> {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    expand_in_buff_with(
+        &mut raw_buff,
+        &mut path_buff,
+        options,
+        paths.map(Path::new),
+        lookup,
+    )
+    .map(Path::to_path_buf)
+}
+
+#[test]
+fn test_expand() {
+    let options = ExpandOptions::default();
+    let cases = [
+        ("~", "/home/me"),
+        ("~/cache/${PROJECT}/out", "/home/me/cache/demo/out"),
+        ("a/~/b", "a/~/b"),
+        ("$PROJECT.d/$PROJECT-x", "demo.d/demo-x"),
+        ("${MISSING:-fallback}/x", "fallback/x"),
+        ("${EMPTY:-fallback}", "fallback"),
+        ("${PROJECT:-fallback}", "demo"),
+        ("${EMPTY}x", "x"),
+        ("cost/$/$5/a$", "cost/$/$5/a$"),
+    ];
+    for (path, expected) in cases {
+        assert_eq!(
+            expand([path], &options),
+            Ok(PathBuf::from(expected)),
+            "{path}"
+        );
+    }
+
+    // Values that don't fit in the stack buffer spill onto the heap
+    let long = lookup("LONG".as_ref()).unwrap();
+    let long = Path::new(&long);
+    assert_eq!(
+        expand(["$LONG/$LONG", "${LONG}"], &options),
+        Ok(long.join(long).join(long))
+    );
+}
+
+#[test]
+fn test_expand_join() {
+    let options = ExpandOptions::default();
+    assert_eq!(
+        expand(["~", "$PROJECT", "out"], &options),
+        Ok(PathBuf::from("/home/me/demo/out"))
+    );
+    assert_eq!(
+        expand(["base", "$ROOT/x", "y"], &options),
+        Ok(PathBuf::from("/srv/x/y"))
+    );
+    assert_eq!(
+        expand(["base", "~", "y"], &options),
+        Ok(PathBuf::from("/home/me/y"))
+    );
+    assert_eq!(
+        expand(["base", "$EMPTY", "y"], &options),
+        Ok(PathBuf::from("base/y"))
+    );
+}
+
+#[test]
+fn test_expand_errors() {
+    let options = ExpandOptions::default();
+    assert_eq!(
+        expand(["$MISSING/x"], &options),
+        Err(ExpandError::UndefinedVariable("MISSING".into()))
+    );
+    assert_eq!(
+        expand(["${MISSING}"], &options),
+        Err(ExpandError::UndefinedVariable("MISSING".into()))
+    );
+    assert_eq!(
+        expand(["${PROJECT"], &options),
+        Err(ExpandError::UnclosedBrace)
+    );
+    assert_eq!(
+        expand(["~path-no-alloc-missing-user/x"], &options),
+        Err(ExpandError::UnknownUser(
+            "path-no-alloc-missing-user".into()
+        ))
+    );
+
+    let strict = ExpandOptions { strict: true };
+    assert_eq!(
+        expand(["~root/x"], &strict),
+        Err(ExpandError::UserLookupDisabled("root".into()))
+    );
+    assert_eq!(expand(["~/x"], &strict), Ok(PathBuf::from("/home/me/x")));
+
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    let no_home = expand_in_buff_with(
+        &mut raw_buff,
+        &mut path_buff,
+        &options,
+        [Path::new("~")],
+        |_| None,
+    );
+    assert_eq!(no_home, Err(ExpandError::NoHome));
+}
+
+#[test]
+fn test_expand_user() {
+    // root's home directory varies between systems, and some containers
+    // don't have a password database at all
+    let options = ExpandOptions::default();
+    let root_home = match expand(["~root"], &options) {
+        Ok(home) => home,
+        Err(ExpandError::UnknownUser(_)) => return,
+        Err(err) => panic!("{err}"),
+    };
+    assert!(root_home.is_absolute());
+    assert_eq!(expand(["~root/x"], &options), Ok(root_home.join("x")));
+}
+
+#[test]
+fn test_with_expanded_paths() {
+    std::env::set_var("PATH_NO_ALLOC_TEST_EXPAND", "value");
+    let base = "/base";
+    let var = "$PATH_NO_ALLOC_TEST_EXPAND/${PATH_NO_ALLOC_TEST_MISSING:-default}";
+    let missing = "$PATH_NO_ALLOC_TEST_MISSING";
+
+    with_expanded_paths! {
+        path = base / var,
+        bad = base / missing
+    };
+    assert_eq!(path, Ok(Path::new("/base/value/default")));
+    assert_eq!(
+        bad,
+        Err(ExpandError::UndefinedVariable(
+            "PATH_NO_ALLOC_TEST_MISSING".into()
+        ))
+    );
+
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    let direct = expand_in_buff(
+        &mut raw_buff,
+        &mut path_buff,
+        &ExpandOptions::default(),
+        [Path::new(var)],
+    );
+    assert_eq!(direct, Ok(Path::new("value/default")));
+}
@@ -0,0 +1,62 @@
+use std::fs;
+
+use crate::{find_up, find_up_with, FindUp};
+
+#[test]
+fn test_find_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let deep = root.join("a/b/c");
+    fs::create_dir_all(&deep).unwrap();
+    fs::write(root.join("marker"), "").unwrap();
+    fs::write(root.join("a/other"), "").unwrap();
+
+    assert_eq!(find_up(&deep, &["marker"]).unwrap(), root.join("marker"));
+    assert_eq!(
+        find_up(&deep, &["marker", "other"]).unwrap(),
+        root.join("a/other")
+    );
+    assert_eq!(find_up(&deep, &["c"]).unwrap(), root.join("a/b/c"));
+    assert!(find_up(&deep, &["path-no-alloc-missing"]).is_none());
+}
+
+#[test]
+fn test_find_up_stops_at_repository() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let repo = root.join("repo");
+    let deep = repo.join("src/nested");
+    fs::create_dir_all(&deep).unwrap();
+    fs::create_dir(repo.join(".git")).unwrap();
+    fs::write(root.join("marker"), "").unwrap();
+    fs::write(repo.join("in-repo"), "").unwrap();
+
+    let options = FindUp {
+        stop_at_repository: true,
+        ..FindUp::default()
+    };
+    assert!(find_up_with(&deep, &["marker"], &options).is_none());
+    assert_eq!(
+        find_up_with(&deep, &["in-repo"], &options).unwrap(),
+        repo.join("in-repo")
+    );
+    assert_eq!(find_up(&deep, &["marker"]).unwrap(), root.join("marker"));
+}
+
+#[test]
+fn test_find_up_same_file_system() {
+    let dir = tempfile::tempdir().unwrap();
+    let deep = dir.path().join("a/b");
+    fs::create_dir_all(&deep).unwrap();
+    fs::write(dir.path().join("marker"), "").unwrap();
+
+    let options = FindUp {
+        same_file_system: true,
+        ..FindUp::default()
+    };
+    assert_eq!(
+        find_up_with(&deep, &["marker"], &options).unwrap(),
+        dir.path().join("marker")
+    );
+    assert!(find_up_with(dir.path().join("missing"), &["marker"], &options).is_none());
+}
@@ -0,0 +1,99 @@
+use std::{fs, os::unix::fs::symlink, path::Path};
+
+use crate::{glob_walk, Pattern};
+
+/// Builds:
+///
+/// ```text
+/// root/
+///   src/
+///     bin/
+///       main.rs
+///     lib.rs
+///     notes.md
+///   target/
+///     debug/
+///       build.rs
+///   Cargo.toml
+///   build.rs
+///   link.rs -> src/lib.rs
+/// ```
+fn tree() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("src/bin")).unwrap();
+    fs::create_dir_all(root.join("target/debug")).unwrap();
+    for file in [
+        "src/bin/main.rs",
+        "src/lib.rs",
+        "src/notes.md",
+        "target/debug/build.rs",
+        "Cargo.toml",
+        "build.rs",
+    ] {
+        fs::write(root.join(file), "").unwrap();
+    }
+    symlink("src/lib.rs", root.join("link.rs")).unwrap();
+    tmp
+}
+
+fn glob(root: &Path, pattern: &str) -> Vec<String> {
+    let pattern = Pattern::new(pattern).unwrap();
+    let mut matches = glob_walk(root, &pattern).unwrap();
+    let mut paths = Vec::new();
+    while let Some(path) = matches.next() {
+        let relative = path.unwrap().strip_prefix(root).unwrap();
+        paths.push(relative.to_str().unwrap().to_owned());
+    }
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_glob_walk() {
+    let tmp = tree();
+    let root = tmp.path();
+    assert_eq!(glob(root, "src/**/*.rs"), ["src/bin/main.rs", "src/lib.rs"]);
+    assert_eq!(
+        glob(root, "**/*.rs"),
+        [
+            "build.rs",
+            "link.rs",
+            "src/bin/main.rs",
+            "src/lib.rs",
+            "target/debug/build.rs"
+        ]
+    );
+    assert_eq!(
+        glob(root, "*.{rs,toml}"),
+        ["Cargo.toml", "build.rs", "link.rs"]
+    );
+    assert_eq!(
+        glob(root, "src/*"),
+        ["src/bin", "src/lib.rs", "src/notes.md"]
+    );
+    assert_eq!(glob(root, "*/*/"), Vec::<String>::new());
+    assert_eq!(glob(root, "[st]*"), ["src", "target"]);
+    assert!(glob(root, "missing/**").is_empty());
+}
+
+#[test]
+fn test_glob_walk_root_with_separator() {
+    let tmp = tree();
+    let root = tmp.path();
+    let pattern = Pattern::new("bin/*.rs").unwrap();
+
+    let src = root.join("src/");
+    let mut matches = glob_walk(&src, &pattern).unwrap();
+    assert_eq!(
+        matches.next().unwrap().unwrap(),
+        root.join("src/bin/main.rs")
+    );
+    assert!(matches.next().is_none());
+}
+
+#[test]
+fn test_glob_walk_missing_root() {
+    let pattern = Pattern::new("*").unwrap();
+    assert!(glob_walk("/definitely/not/here", &pattern).is_err());
+}
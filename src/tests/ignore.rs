@@ -0,0 +1,124 @@
+use std::fs;
+
+use crate::{with_paths, Ignore};
+
+fn ignore(rules: &str) -> Ignore {
+    let mut ignore = Ignore::new("");
+    ignore.add_rules(rules);
+    ignore
+}
+
+#[test]
+fn test_ignore_unanchored() {
+    let ignore = ignore("*.o\nbuild\n");
+    assert!(ignore.is_ignored("main.o", false));
+    assert!(ignore.is_ignored("src/deep/main.o", false));
+    assert!(ignore.is_ignored("build", true));
+    assert!(ignore.is_ignored("src/build", false));
+    assert!(ignore.is_ignored("src/build/out.txt", false));
+    assert!(!ignore.is_ignored("main.c", false));
+    assert!(!ignore.is_ignored("builder", true));
+}
+
+#[test]
+fn test_ignore_anchored() {
+    let ignore = ignore("/TODO\ndoc/*.html\n");
+    assert!(ignore.is_ignored("TODO", false));
+    assert!(!ignore.is_ignored("src/TODO", false));
+    assert!(ignore.is_ignored("doc/index.html", false));
+    assert!(!ignore.is_ignored("doc/api/index.html", false));
+    assert!(!ignore.is_ignored("src/doc/index.html", false));
+}
+
+#[test]
+fn test_ignore_dir_only() {
+    let ignore = ignore("target/\nlogs/**\n");
+    assert!(ignore.is_ignored("target", true));
+    assert!(!ignore.is_ignored("target", false));
+    assert!(ignore.is_ignored("crates/a/target/debug/app", false));
+    assert!(ignore.is_ignored("logs/a/b.log", false));
+    assert!(!ignore.is_ignored("logs", true));
+}
+
+#[test]
+fn test_ignore_negation() {
+    let ignore = ignore("*.log\n!keep.log\ntarget/\n!target/keep.txt\n");
+    assert!(ignore.is_ignored("debug.log", false));
+    assert!(!ignore.is_ignored("keep.log", false));
+    assert!(!ignore.is_ignored("logs/keep.log", false));
+    // A file can't be re-included if its directory is ignored
+    assert!(ignore.is_ignored("target/keep.txt", false));
+
+    let ignore = self::ignore("/*\n!/src\n");
+    assert!(ignore.is_ignored("README.md", false));
+    assert!(!ignore.is_ignored("src", true));
+    assert!(!ignore.is_ignored("src/lib.rs", false));
+}
+
+#[test]
+fn test_ignore_repeated_separators() {
+    let ignore = ignore("hello/world\n/doc/*.html\n!/doc//keep.html\ntarget/\n");
+    assert!(ignore.is_ignored("hello//world", false));
+    assert!(ignore.is_ignored("doc//index.html", false));
+    assert!(!ignore.is_ignored("doc///keep.html", false));
+    assert!(ignore.is_ignored("a//target//debug", false));
+
+    let dir = "hello/";
+    let name = "world";
+    with_paths! {
+        path = dir / name
+        => assert!(ignore.is_ignored(path, false))
+    }
+
+    let mut rooted = Ignore::new("/srv/app");
+    rooted.add_rules("hello/world\n");
+    assert!(rooted.is_ignored("/srv/app//hello//world", false));
+}
+
+#[test]
+fn test_ignore_syntax() {
+    let ignore = ignore(
+        "# comment\n\n\\#hash\n\\!bang\ntrailing   \nspace\\ \n\
+         [abc].txt\n{a,b}\nbad[\r\nglob/**/deep\n",
+    );
+    assert!(!ignore.is_ignored("# comment", false));
+    assert!(ignore.is_ignored("#hash", false));
+    assert!(ignore.is_ignored("!bang", false));
+    assert!(ignore.is_ignored("trailing", false));
+    assert!(!ignore.is_ignored("trailing ", false));
+    assert!(ignore.is_ignored("space ", false));
+    assert!(!ignore.is_ignored("space", false));
+    assert!(ignore.is_ignored("b.txt", false));
+    // No brace alternation
+    assert!(ignore.is_ignored("{a,b}", false));
+    assert!(!ignore.is_ignored("a", false));
+    assert!(!ignore.is_ignored("bad[", false));
+    assert!(ignore.is_ignored("glob/deep", false));
+    assert!(ignore.is_ignored("glob/x/y/deep", false));
+}
+
+#[test]
+fn test_ignore_from_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::write(root.join(".gitignore"), "*.tmp\n/vendor/\n").unwrap();
+    fs::write(root.join(".ignore"), "!important.tmp\n").unwrap();
+
+    let ignore = Ignore::from_dir(root).unwrap();
+    assert_eq!(ignore.root(), root);
+    let (src, scratch, important, vendored) =
+        ("src", "scratch.tmp", "important.tmp", "vendor/lib/a.rs");
+    with_paths! {
+        scratch = root / src / scratch,
+        important = root / important,
+        vendored = root / vendored
+    };
+    assert!(ignore.is_ignored(scratch, false));
+    assert!(!ignore.is_ignored(important, false));
+    assert!(ignore.is_ignored(vendored, false));
+    assert!(ignore.is_ignored("vendor", true));
+
+    let empty = tempfile::tempdir().unwrap();
+    let ignore = Ignore::from_dir(empty.path()).unwrap();
+    assert!(!ignore.is_ignored("anything", false));
+}
@@ -0,0 +1,73 @@
+use std::mem::MaybeUninit;
+
+use crate::{join_key_in_buff, with_keys, KeyError, KeyOptions};
+
+fn key<const N: usize>(options: &KeyOptions, segments: [&str; N]) -> Result<String, KeyError> {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut str_buff = None;
+    join_key_in_buff(&mut raw_buff, &mut str_buff, options, segments).map(str::to_owned)
+}
+
+#[test]
+fn test_keys_leading_slash_does_not_reset() {
+    let prefix = "exports";
+    let tenant = "/acme/";
+    let object = "/data.json";
+    with_keys! {
+        key = prefix / tenant / object
+        => assert_eq!(key, Ok("exports/acme/data.json"))
+    }
+}
+
+#[test]
+fn test_keys_empty_segments() {
+    let options = KeyOptions::default();
+    assert_eq!(key(&options, ["a", "", "/", "b//c"]), Ok("a/b/c".into()));
+    assert_eq!(key(&options, ["", ""]), Ok("".into()));
+}
+
+#[test]
+fn test_keys_dot_segments() {
+    let options = KeyOptions::default();
+    assert_eq!(key(&options, ["a", "b/../c"]), Err(KeyError::DotSegment));
+    assert_eq!(key(&options, ["a", "./c"]), Err(KeyError::DotSegment));
+    assert_eq!(key(&options, ["a", "..b", "c."]), Ok("a/..b/c.".into()));
+
+    let permissive = KeyOptions {
+        reject_dot_segments: false,
+        ..KeyOptions::default()
+    };
+    assert_eq!(key(&permissive, ["a", "b/../c"]), Ok("a/b/../c".into()));
+}
+
+#[test]
+fn test_keys_untrimmed() {
+    let options = KeyOptions {
+        trim: false,
+        ..KeyOptions::default()
+    };
+    assert_eq!(key(&options, ["a/", "", "/b"]), Ok("a///b".into()));
+}
+
+#[test]
+fn test_keys_separator() {
+    let options = KeyOptions {
+        separator: ':',
+        ..KeyOptions::default()
+    };
+    assert_eq!(
+        key(&options, [":cache:", "user", "42"]),
+        Ok("cache:user:42".into())
+    );
+    assert_eq!(key(&options, ["a/b", "c"]), Ok("a/b:c".into()));
+}
+
+#[test]
+fn test_keys_overflow() {
+    let p1 = "Call me Ishmael. Some years ago—never mind how long precisely—having little or no money in my purse";
+    let p2 = "and nothing particular to interest me on shore";
+    assert_eq!(
+        key(&KeyOptions::default(), [p1, p2]),
+        Ok(format!("{p1}/{p2}"))
+    );
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use path_no_alloc_derive::PathLayout;
+
+use crate::{PathLayout as _, StackPathBuf};
+
+#[derive(PathLayout)]
+struct Cache {
+    #[layout(artifact = "{hash}/artifact.bin", index = "index")]
+    dir: PathBuf,
+}
+
+#[derive(PathLayout)]
+struct Layout<'a> {
+    #[layout(
+        build_log = "projects/{project}/builds/{id}.log",
+        manifest = "Cargo.toml",
+        pair = "{a}/{a}-{b}.txt",
+        braces = "{{{f}}}"
+    )]
+    root: &'a Path,
+    #[layout(root)]
+    scratch: StackPathBuf,
+    #[layout(nested)]
+    cache: Cache,
+    #[allow(dead_code)]
+    name: String,
+}
+
+fn layout() -> Layout<'static> {
+    Layout {
+        root: Path::new("/srv/app"),
+        scratch: StackPathBuf::from_path("/tmp/scratch"),
+        cache: Cache {
+            dir: "cache".into(),
+        },
+        name: "app".into(),
+    }
+}
+
+#[test]
+fn test_layout_accessors() {
+    let layout = layout();
+    assert_eq!(
+        layout.build_log("crate", "42"),
+        Path::new("/srv/app/projects/crate/builds/42.log")
+    );
+    assert_eq!(layout.manifest(), Path::new("/srv/app/Cargo.toml"));
+    assert_eq!(layout.pair("x", "y"), Path::new("/srv/app/x/x-y.txt"));
+    assert_eq!(layout.braces("f"), Path::new("/srv/app/{f}"));
+    assert_eq!(
+        layout.cache.artifact("abc"),
+        Path::new("cache/abc/artifact.bin")
+    );
+    assert_eq!(layout.cache.index(), Path::new("cache/index"));
+
+    let len = layout.with_build_log("crate", "42", |path| path.as_os_str().len());
+    assert_eq!(len, "/srv/app/projects/crate/builds/42.log".len());
+
+    // Same rules as `join_in_buff`
+    assert_eq!(
+        layout.build_log("/elsewhere", "1"),
+        Path::new("/elsewhere/builds/1.log")
+    );
+    let long = "p".repeat(200);
+    assert_eq!(
+        layout.build_log(&long, "1"),
+        Path::new("/srv/app/projects")
+            .join(&long)
+            .join("builds/1.log")
+    );
+}
+
+#[test]
+fn test_layout_roots() {
+    let layout = layout();
+    let mut roots = Vec::new();
+    layout.visit_roots(&mut |root| roots.push(root.to_owned()));
+    assert_eq!(
+        roots,
+        [
+            PathBuf::from("/srv/app"),
+            PathBuf::from("/tmp/scratch"),
+            PathBuf::from("cache")
+        ]
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache {
+        dir: dir.path().join("a/b/cache"),
+    };
+    cache.create_dirs().unwrap();
+    assert!(cache.dir.is_dir());
+}
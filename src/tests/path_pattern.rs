@@ -0,0 +1,124 @@
+use std::{ffi::OsStr, os::unix::ffi::OsStrExt, path::Path};
+
+use crate::{PathPattern, PathRouter, TemplateError};
+
+#[test]
+fn test_path_pattern_captures() {
+    let pattern = PathPattern::new("{root}/projects/{project}/builds/{build_id}.log").unwrap();
+    assert_eq!(
+        pattern.names().collect::<Vec<_>>(),
+        ["root", "project", "build_id"]
+    );
+
+    let captures = pattern
+        .captures("/srv/projects/crate/builds/42.log")
+        .unwrap();
+    assert_eq!(captures.get("root").unwrap(), "/srv");
+    assert_eq!(captures.get("project").unwrap(), "crate");
+    assert_eq!(captures.parse::<u64>("build_id"), Some(42));
+    assert_eq!(captures.parse::<u64>("project"), None);
+    assert_eq!(captures.get("missing"), None);
+    assert_eq!(
+        captures.iter().collect::<Vec<_>>(),
+        [
+            ("root", OsStr::new("/srv")),
+            ("project", OsStr::new("crate")),
+            ("build_id", OsStr::new("42"))
+        ]
+    );
+
+    // Only the leading placeholder can span components
+    let captures = pattern
+        .captures("a/b/projects/x/projects/y/builds/1.2.log")
+        .unwrap();
+    assert_eq!(captures.get_str("root"), Some("a/b/projects/x"));
+    assert_eq!(captures.get_str("project"), Some("y"));
+    assert_eq!(captures.get_str("build_id"), Some("1.2"));
+    assert!(pattern.captures("/srv/projects/a/b/builds/1.log").is_none());
+    assert!(pattern.captures("projects/crate/builds/1.log").is_none());
+    assert!(pattern
+        .captures("/srv/projects/crate/builds/.log")
+        .is_none());
+}
+
+#[test]
+fn test_path_pattern_literals() {
+    let pattern = PathPattern::new("/etc/{name}.conf").unwrap();
+    assert!(pattern.is_match("/etc/app.conf"));
+    assert!(!pattern.is_match("etc/app.conf"));
+    assert!(!pattern.is_match("/etc/sub/app.conf"));
+    assert!(!pattern.is_match("/etc/app.conf/"));
+
+    let pattern = PathPattern::new("fixed/path").unwrap();
+    assert!(pattern.is_match("fixed/path"));
+    assert!(!pattern.is_match("fixed/path/x"));
+    assert_eq!(pattern.captures("fixed/path").unwrap().iter().count(), 0);
+
+    let pattern = PathPattern::new("{{{a}}}").unwrap();
+    assert_eq!(pattern.captures("{x}").unwrap().get_str("a"), Some("x"));
+}
+
+#[test]
+fn test_path_pattern_backtracking() {
+    // Earlier placeholders take as much as they can
+    let pattern = PathPattern::new("{name}.{ext}").unwrap();
+    let captures = pattern.captures("archive.tar.gz").unwrap();
+    assert_eq!(captures.get_str("name"), Some("archive.tar"));
+    assert_eq!(captures.get_str("ext"), Some("gz"));
+
+    // Repeated placeholders have to agree
+    let pattern = PathPattern::new("{a}/{b}-{a}").unwrap();
+    let captures = pattern.captures("x/y-z-x").unwrap();
+    assert_eq!(captures.get_str("b"), Some("y-z"));
+    assert!(pattern.captures("x/y-z").is_none());
+}
+
+#[test]
+fn test_path_pattern_non_utf8() {
+    let pattern = PathPattern::new("data/{name}").unwrap();
+    let path = Path::new(OsStr::from_bytes(b"data/\xff\xfe"));
+    let captures = pattern.captures(path).unwrap();
+    assert_eq!(captures.get("name").unwrap().as_bytes(), b"\xff\xfe");
+    assert_eq!(captures.get_str("name"), None);
+}
+
+#[test]
+fn test_path_pattern_errors() {
+    let many = (0..17)
+        .map(|i| format!("{{p{i}}}"))
+        .collect::<Vec<_>>()
+        .join("/");
+    assert_eq!(
+        PathPattern::new(&many).unwrap_err(),
+        TemplateError::TooManyPlaceholders
+    );
+    let repeated = vec!["{p}"; 20].join("/");
+    assert!(PathPattern::new(&repeated).is_ok());
+    assert_eq!(
+        PathPattern::new("{a}{b}").unwrap_err(),
+        TemplateError::AdjacentPlaceholders("a".to_owned(), "b".to_owned())
+    );
+}
+
+#[test]
+fn test_path_router() {
+    let mut router = PathRouter::new();
+    router.add(PathPattern::new("{root}/builds/{id}.log").unwrap(), 1);
+    router.add(PathPattern::new("{root}/builds/{name}").unwrap(), 2);
+    router.add(PathPattern::new("/tmp/{file}").unwrap(), 3);
+
+    let (route, captures) = router.route("/srv/builds/7.log").unwrap();
+    assert_eq!(*route, 1);
+    assert_eq!(captures.parse::<u32>("id"), Some(7));
+
+    let (route, captures) = router.route("/srv/builds/7.txt").unwrap();
+    assert_eq!(*route, 2);
+    assert_eq!(captures.get_str("name"), Some("7.txt"));
+
+    let (route, captures) = router.route(Path::new("/tmp/x")).unwrap();
+    assert_eq!(*route, 3);
+    assert_eq!(captures.get_str("file"), Some("x"));
+
+    assert!(router.route("/srv/other").is_none());
+    assert!(PathRouter::<()>::default().route("a").is_none());
+}
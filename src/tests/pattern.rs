@@ -0,0 +1,209 @@
+use std::{
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{with_paths, Pattern, PatternError};
+
+fn check(pattern: &str, matching: &[&str], not_matching: &[&str]) {
+    let compiled = Pattern::new(pattern).unwrap();
+    for path in matching {
+        assert!(compiled.matches(path), "{pattern} should match {path}");
+    }
+    for path in not_matching {
+        assert!(!compiled.matches(path), "{pattern} shouldn't match {path}");
+    }
+}
+
+#[test]
+fn test_pattern_wildcards() {
+    check(
+        "*.rs",
+        &["lib.rs", ".rs", "é.rs"],
+        &["src/lib.rs", "lib.rsx"],
+    );
+    check(
+        "src/?.rs",
+        &["src/a.rs", "src/é.rs"],
+        &["src/ab.rs", "src/.rs", "src//.rs"],
+    );
+    check("a*b*c", &["abc", "a-b-c", "abbbc"], &["ab", "a/b/c"]);
+    check("src/lib.rs", &["src/lib.rs"], &["src/lib.r", "src/lib.rs/"]);
+}
+
+#[test]
+fn test_pattern_globstar() {
+    check(
+        "**/*.rs",
+        &["lib.rs", "src/lib.rs", "src/tests/walk.rs"],
+        &["lib.toml", "src/lib.rs/x"],
+    );
+    check(
+        "src/**/mod.rs",
+        &["src/mod.rs", "src/a/b/mod.rs"],
+        &["mod.rs", "srcx/mod.rs"],
+    );
+    check(
+        "target/**",
+        &["target/debug", "target/a/b/c"],
+        &["target", "targets/a"],
+    );
+    check("**", &["", "a", "a/b/c"], &[]);
+    // Only a whole component is a globstar
+    check("a**b", &["ab", "axxb"], &["a/b", "ax/xb"]);
+}
+
+#[test]
+fn test_pattern_classes() {
+    check("[abc].txt", &["a.txt", "c.txt"], &["d.txt", "ab.txt"]);
+    check("[a-cx-z]", &["b", "y"], &["d", "w", "/"]);
+    check("[!a-c]", &["d", "é"], &["a", "/", ""]);
+    check("[^a-c]", &["d"], &["b"]);
+    check("[]a]", &["]", "a"], &["b"]);
+    check("[a-]", &["a", "-"], &["b"]);
+    check("[\\]]", &["]"], &["\\"]);
+    check("[é-ë]", &["ê"], &["e"]);
+}
+
+#[test]
+fn test_pattern_braces() {
+    check(
+        "src/*.{rs,toml}",
+        &["src/lib.rs", "src/Cargo.toml"],
+        &["src/lib.md", "src/lib.{rs,toml}"],
+    );
+    check("{a,b{c,d}}.txt", &["a.txt", "bc.txt", "bd.txt"], &["b.txt"]);
+    check("x{,y}", &["x", "xy"], &["xyy"]);
+    check("\\{a,b\\}", &["{a,b}"], &["a"]);
+    check("[{]a}", &["{a}"], &["a"]);
+}
+
+#[test]
+fn test_pattern_escapes() {
+    check("\\*", &["*"], &["a"]);
+    check("a\\?", &["a?"], &["ab"]);
+    check("\\[a]", &["[a]"], &["a"]);
+}
+
+#[test]
+fn test_pattern_errors() {
+    assert_eq!(
+        Pattern::new("[abc").unwrap_err(),
+        PatternError::UnclosedClass
+    );
+    assert_eq!(
+        Pattern::new("{a,b").unwrap_err(),
+        PatternError::UnclosedBrace
+    );
+    assert_eq!(
+        Pattern::new("abc\\").unwrap_err(),
+        PatternError::TrailingEscape
+    );
+    assert_eq!(
+        Pattern::new("{a,b}{a,b}{a,b}{a,b}{a,b}{a,b}{a,b}{a,b}{a,b}").unwrap_err(),
+        PatternError::TooManyAlternatives
+    );
+    // A stray closing brace is just a character
+    check("a}", &["a}"], &["a"]);
+}
+
+#[test]
+fn test_pattern_pathological() {
+    let start = Instant::now();
+    let input = "a".repeat(60);
+    assert!(!Pattern::new("*a*a*a*a*a*a*a*b").unwrap().matches(&input));
+    assert!(Pattern::new("*a*a*a*a*a*a*a*a").unwrap().matches(&input));
+
+    let input = "a/".repeat(60) + "c";
+    assert!(!Pattern::new("**/a/**/a/**/a/**/a/**/b")
+        .unwrap()
+        .matches(&input));
+    assert!(Pattern::new("**/a/**/a/**/a/**/a/**/c")
+        .unwrap()
+        .matches(&input));
+    assert!(!Pattern::new("**/*a*a*a*a*/**/*a*b")
+        .unwrap()
+        .matches(&input));
+    assert!(Pattern::new("**/*a*a*a*a*b")
+        .unwrap()
+        .could_match_within(&input));
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // Backtracking still finds matches that need it
+    check("*a*b", &["xaxab", "aab"], &["xaxa"]);
+    check("**/a/b", &["a/a/b", "a/a/a/b"], &["a/b/b"]);
+    check("a*/b", &["ax/b"], &["ax/c", "a/x/b"]);
+}
+
+#[test]
+fn test_pattern_segments() {
+    let pattern = Pattern::new("src/**/*.rs").unwrap();
+    let root = Path::new("src");
+    assert!(pattern.matches_segments(&[root, "tests".as_ref(), "walk.rs".as_ref()]));
+    assert!(pattern.matches_segments(&["src/".as_ref(), "lib.rs".as_ref()]));
+    assert!(!pattern.matches_segments(&[root, "/lib.rs".as_ref()]));
+
+    // Empty segments are skipped, as in `join_in_buff`
+    assert!(pattern.matches_segments(&[root, "lib.rs".as_ref(), "".as_ref()]));
+    assert!(!Pattern::new("src/")
+        .unwrap()
+        .matches_segments(&[root, "".as_ref()]));
+
+    let long = "a".repeat(300);
+    assert!(pattern.matches_segments(&[root, long.as_ref(), "main.rs".as_ref()]));
+
+    let name = "lib.rs";
+    with_paths! {
+        path = root / name
+        => assert!(pattern.matches(path))
+    }
+}
+
+#[test]
+fn test_pattern_repeated_separators() {
+    check(
+        "src/**/*.rs",
+        &["src//lib.rs", "src//bin///main.rs", "src/bin//main.rs"],
+        &["src//lib.rs/", "src//"],
+    );
+    check("a//b", &["a/b", "a//b"], &["ab", "a/b/"]);
+    check("a/**//b", &["a/b", "a//x//b"], &["a/xb"]);
+    check("a?/b", &[], &["a//b"]);
+    check("[a/]/b", &[], &["///b", "a///"]);
+
+    // `with_paths!` keeps both slashes when a segment ends with one
+    let pattern = Pattern::new("hello/*").unwrap();
+    let dir = "hello/";
+    let name = "world";
+    with_paths! {
+        path = dir / name
+    };
+    assert_eq!(path.as_os_str().as_bytes(), b"hello//world");
+    assert!(pattern.matches(path));
+    assert!(pattern.matches_segments(&[dir.as_ref(), name.as_ref()]));
+
+    let pattern = Pattern::new("src/{tests,bin}/*.rs").unwrap();
+    assert!(pattern.could_match_within("src//tests//"));
+    assert!(!pattern.could_match_within("src//tests//data"));
+}
+
+#[test]
+fn test_pattern_could_match_within() {
+    let pattern = Pattern::new("src/{tests,bin}/*.rs").unwrap();
+    assert!(pattern.could_match_within(""));
+    assert!(pattern.could_match_within("src"));
+    assert!(pattern.could_match_within("src/tests/"));
+    assert!(!pattern.could_match_within("target"));
+    assert!(!pattern.could_match_within("src/benches"));
+    // Files directly in `src/tests` can match, but nothing deeper
+    assert!(!pattern.could_match_within("src/tests/data"));
+
+    let pattern = Pattern::new("**/*.rs").unwrap();
+    assert!(pattern.could_match_within("a/b/c"));
+
+    let pattern = Pattern::new("s*/[a-c]x/**").unwrap();
+    assert!(pattern.could_match_within("src/bx"));
+    assert!(pattern.could_match_within("src/bx/deep/er"));
+    assert!(!pattern.could_match_within("src/dx"));
+}
@@ -0,0 +1,120 @@
+use std::mem::MaybeUninit;
+
+use rand::{distributions::Uniform, prelude::Distribution};
+
+use crate::{join_posix_in_buff, with_posix_paths, PosixPath};
+
+#[test]
+fn test_posix_join() {
+    let p1 = "hello";
+    let p2 = "world/";
+    let p3 = "some/other/path";
+    with_posix_paths! {
+        path = p1 / p2 / p3 => assert_eq!(path.as_bytes(), b"hello/world/some/other/path")
+    }
+}
+
+#[test]
+fn test_posix_join_absolute() {
+    let p1 = "hello";
+    let p2 = String::from("/world");
+    let p3 = b"path".to_vec();
+    with_posix_paths! {
+        path = p1 / p2 / p3
+    };
+    assert_eq!(path.to_str(), Some("/world/path"));
+    assert!(path.is_absolute());
+}
+
+#[test]
+fn test_posix_backslash_is_not_a_separator() {
+    let p1 = r"C:\Users";
+    let p2 = r"\\server\share";
+    with_posix_paths! {
+        path = p1 / p2
+    };
+    assert_eq!(path.to_str(), Some(r"C:\Users/\\server\share"));
+    assert!(!path.is_absolute());
+    assert_eq!(path.components().count(), 2);
+}
+
+#[test]
+fn test_posix_overflow() {
+    let p1 = "Call me Ishmael. Some years ago—never mind how long precisely—having little or no money in my purse";
+    let p2 = "and nothing particular to interest me on shore";
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut bytes_buff = None;
+    let path = join_posix_in_buff(&mut raw_buff, &mut bytes_buff, [p1.as_ref(), p2.as_ref()]);
+    assert_eq!(path.to_str(), Some(format!("{p1}/{p2}").as_str()));
+    assert!(bytes_buff.is_some());
+}
+
+#[test]
+fn test_posix_parent_and_file_name() {
+    let cases: [(&str, Option<&str>, Option<&str>); 10] = [
+        ("a/b/c", Some("a/b"), Some("c")),
+        ("a/b/c/", Some("a/b"), Some("c")),
+        ("a/b/.", Some("a"), Some("b")),
+        ("/a", Some("/"), Some("a")),
+        ("//a", Some("/"), Some("a")),
+        ("a", Some(""), Some("a")),
+        ("a/..", Some("a"), None),
+        ("/", None, None),
+        ("", None, None),
+        (".", None, None),
+    ];
+    for (path, parent, file_name) in cases {
+        let path = PosixPath::new(path);
+        assert_eq!(
+            path.parent().and_then(PosixPath::to_str),
+            parent,
+            "{path:?}"
+        );
+        assert_eq!(
+            path.file_name().map(|f| std::str::from_utf8(f).unwrap()),
+            file_name,
+            "{path:?}"
+        );
+    }
+}
+
+/// Joining POSIX paths should produce exactly the same bytes as `PathBuf`
+/// does on unix.
+#[cfg(target_family = "unix")]
+#[test]
+fn test_posix_fuzz() {
+    use std::{os::unix::ffi::OsStrExt, path::PathBuf};
+
+    let mut rng = rand::thread_rng();
+    const SAMPLES: usize = 20_000;
+
+    let options = b"ab./";
+    let length_dist = Uniform::from(0..40);
+    let opt_dist = Uniform::from(0..options.len());
+
+    let segment = |rng: &mut rand::rngs::ThreadRng| -> String {
+        let len = length_dist.sample(rng);
+        opt_dist
+            .sample_iter(rng)
+            .take(len)
+            .map(|i| options[i] as char)
+            .collect()
+    };
+
+    for _ in 0..SAMPLES {
+        let p1 = segment(&mut rng);
+        let p2 = segment(&mut rng);
+        let p3 = segment(&mut rng);
+        let p4 = segment(&mut rng);
+
+        let mut expected = PathBuf::new();
+        for p in [&p1, &p2, &p3, &p4] {
+            expected.push(p);
+        }
+
+        with_posix_paths! {
+            path = p1 / p2 / p3 / p4
+            => assert_eq!(path.as_bytes(), expected.as_os_str().as_bytes())
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    os::unix::fs::{symlink, MetadataExt},
+    path::{Path, PathBuf},
+};
+
+use crate::{read_dir_joined, FileType};
+
+fn read_all(dir: &Path) -> BTreeMap<PathBuf, (FileType, u64)> {
+    let mut entries = read_dir_joined(dir).unwrap();
+    let mut result = BTreeMap::new();
+    while let Some(entry) = entries.next() {
+        let entry = entry.unwrap();
+        assert_eq!(entry.path(), dir.join(entry.file_name()));
+        assert_eq!(
+            entry.c_path().to_bytes(),
+            entry.path().as_os_str().as_encoded_bytes()
+        );
+        result.insert(entry.path().to_owned(), (entry.file_type(), entry.ino()));
+    }
+    result
+}
+
+#[test]
+fn test_read_dir_joined() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    fs::write(dir.join("file"), "").unwrap();
+    fs::create_dir(dir.join("sub")).unwrap();
+    symlink("file", dir.join("link")).unwrap();
+
+    let entries = read_all(dir);
+    let types: Vec<_> = entries.values().map(|(t, _)| *t).collect();
+    // Some filesystems don't report types
+    if !types.contains(&FileType::Unknown) {
+        assert_eq!(types, [FileType::File, FileType::Symlink, FileType::Dir]);
+    }
+    for (path, (_, ino)) in &entries {
+        assert_eq!(*ino, fs::symlink_metadata(path).unwrap().ino());
+    }
+
+    assert!(read_all(&dir.join("sub")).is_empty());
+    assert!(read_dir_joined(dir.join("file")).is_err());
+    assert!(read_dir_joined(dir.join("missing")).is_err());
+}
+
+#[test]
+fn test_read_dir_joined_many() {
+    // Enough entries, with long enough names, to need several getdents64
+    // calls and to spill the path onto the heap
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let long = "x".repeat(150);
+    let mut expected: Vec<_> = (0..2000).map(|i| dir.join(format!("{i}-{long}"))).collect();
+    for path in &expected {
+        fs::write(path, "").unwrap();
+    }
+    expected.sort();
+
+    let found: Vec<_> = read_all(dir).into_keys().collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn test_read_dir_joined_trailing_slash() {
+    let tmp = tempfile::tempdir().unwrap();
+    fs::write(tmp.path().join("file"), "").unwrap();
+    let dir = format!("{}/", tmp.path().display());
+
+    let mut entries = read_dir_joined(&dir).unwrap();
+    let entry = entries.next().unwrap().unwrap();
+    assert_eq!(entry.path(), tmp.path().join("file"));
+    assert_eq!(entry.file_name(), "file");
+    assert!(entries.next().is_none());
+}
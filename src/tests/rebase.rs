@@ -0,0 +1,64 @@
+use std::{mem::MaybeUninit, path::Path};
+
+use crate::{rebase_in_buff, with_paths};
+
+fn rebase(path: &str, old_root: &str, new_root: &str) -> Option<String> {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    rebase_in_buff(
+        &mut raw_buff,
+        &mut path_buff,
+        path.as_ref(),
+        old_root.as_ref(),
+        new_root.as_ref(),
+    )
+    .ok()
+    .map(|p| p.to_str().unwrap().to_owned())
+}
+
+#[test]
+fn test_rebase() {
+    let cases = [
+        ("/a/b/c.txt", "/a", "/x/y", Some("/x/y/b/c.txt")),
+        ("/a/b/c.txt", "/a/", "x", Some("x/b/c.txt")),
+        ("/a/b", "/a/b", "/x", Some("/x")),
+        ("a/./b", "a", "x/", Some("x/b")),
+        ("/a/b", "/", "/x", Some("/x/a/b")),
+        ("/ab/c", "/a", "/x", None),
+        ("/a/b", "a", "/x", None),
+        ("../a", "..", "/x", Some("/x/a")),
+    ];
+    for (path, old_root, new_root, expected) in cases {
+        assert_eq!(
+            rebase(path, old_root, new_root).as_deref(),
+            expected,
+            "{path} from {old_root} to {new_root}"
+        );
+    }
+}
+
+#[test]
+fn test_rebase_operator() {
+    let old_root = "/home/alecto/project";
+    let new_root = "/mnt/backup/project";
+    let files = ["src/lib.rs", "Cargo.toml", "docs/with_paths.md"];
+
+    for file in files {
+        with_paths! {
+            path = old_root / file,
+            moved = rebase(path, old_root, new_root)
+            => assert_eq!(moved, Ok(Path::new(new_root).join(file).as_path()))
+        }
+    }
+}
+
+#[test]
+fn test_rebase_overflow() {
+    let old_root = "/srv";
+    let new_root = "/Call me Ishmael. Some years ago—never mind how long precisely—having little or no money in my purse";
+    let path = "/srv/and nothing particular to interest me on shore";
+    assert_eq!(
+        rebase(path, old_root, new_root),
+        Some(format!("{new_root}{}", &path[old_root.len()..]))
+    );
+}
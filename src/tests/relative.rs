@@ -0,0 +1,114 @@
+use std::{
+    mem::MaybeUninit,
+    path::{Component, Path, PathBuf},
+};
+
+use rand::{distributions::Uniform, prelude::Distribution};
+
+use crate::{relative_to_in_buff, with_paths};
+
+fn relative(target: &str, base: &str) -> Option<PathBuf> {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    relative_to_in_buff(
+        &mut raw_buff,
+        &mut path_buff,
+        target.as_ref(),
+        base.as_ref(),
+    )
+    .map(Path::to_path_buf)
+}
+
+#[test]
+fn test_relative_to() {
+    let cases = [
+        ("/a/b/c", "/a/b", Some("c")),
+        ("/a/b", "/a/b/c", Some("..")),
+        ("/a/b/c", "/a/d/e", Some("../../b/c")),
+        ("/a/b", "/a/b", Some("")),
+        ("/a/b", "/", Some("a/b")),
+        ("/", "/a/b", Some("../..")),
+        ("a/b", "a/c", Some("../b")),
+        ("./a/b", "a/./c/", Some("../b")),
+        ("a", "", Some("a")),
+        ("/a/b", "c", Some("/a/b")),
+        ("a/b", "/c", None),
+        ("a", "../b", None),
+        ("a/b", "a/../c", None),
+        ("../a", "../b", Some("../a")),
+    ];
+    for (target, base, expected) in cases {
+        assert_eq!(
+            relative(target, base),
+            expected.map(PathBuf::from),
+            "{target} relative to {base}"
+        );
+    }
+}
+
+#[test]
+fn test_relative_operator() {
+    let target = "/srv/app/releases/42/bin";
+    let base = "/srv/app/current";
+    let file = "tool";
+
+    with_paths! {
+        link = relative(target, base),
+        full = target / file
+    };
+    assert_eq!(link, Some(Path::new("../releases/42/bin")));
+    assert_eq!(full, Path::new("/srv/app/releases/42/bin/tool"));
+
+    let exists = with_paths! {
+        path = base / file,
+        link = relative(Path::new(target).join(file), base)
+        => assert_eq!(link, Some(Path::new("../releases/42/bin/tool")));
+        path.exists()
+    };
+    assert!(!exists);
+}
+
+/// Lexically resolves `..` and `.` in a path that doesn't climb above its
+/// start.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => assert!(result.pop()),
+            Component::CurDir => {}
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[test]
+fn test_relative_fuzz() {
+    let mut rng = rand::thread_rng();
+    const SAMPLES: usize = 20_000;
+
+    let options = ["a", "b", "ab", "/", "/", "./"];
+    let length_dist = Uniform::from(0..12);
+    let opt_dist = Uniform::from(0..options.len());
+
+    let segment = |rng: &mut rand::rngs::ThreadRng| -> String {
+        let len = length_dist.sample(rng);
+        opt_dist
+            .sample_iter(rng)
+            .take(len)
+            .map(|i| options[i])
+            .collect()
+    };
+
+    for _ in 0..SAMPLES {
+        let target = format!("/{}", segment(&mut rng));
+        let base = format!("/{}", segment(&mut rng));
+
+        let rel = relative(&target, &base).unwrap();
+        assert_eq!(
+            normalize(&Path::new(&base).join(&rel)),
+            normalize(Path::new(&target)),
+            "{target} relative to {base} gave {rel:?}"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    fs, io,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use crate::{canonicalize_into, read_link_into, StackPathBuf};
+
+#[test]
+fn test_read_link_into() {
+    let dir = tempfile::tempdir().unwrap();
+    let short = dir.path().join("short");
+    let long = dir.path().join("long");
+    let long_target = "x/".repeat(100);
+    symlink("../target", &short).unwrap();
+    symlink(&long_target, &long).unwrap();
+
+    let mut out = StackPathBuf::<16>::from_path("leftover");
+    read_link_into(&short, &mut out).unwrap();
+    assert_eq!(out, Path::new("../target"));
+    assert!(out.is_inline());
+
+    read_link_into(&long, &mut out).unwrap();
+    assert_eq!(out, Path::new(&long_target));
+    assert!(!out.is_inline());
+
+    let mut exact = StackPathBuf::<10>::new();
+    read_link_into(&short, &mut exact).unwrap();
+    assert_eq!(exact, Path::new("../target"));
+
+    let err = read_link_into(dir.path(), &mut out).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+}
+
+fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    let mut out = StackPathBuf::<32>::new();
+    canonicalize_into(path, &mut out).map(|()| out.into_path_buf())
+}
+
+#[test]
+fn test_canonicalize_into() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = fs::canonicalize(tmp.path()).unwrap();
+    fs::create_dir_all(root.join("a/b/c")).unwrap();
+    fs::write(root.join("a/b/file"), "").unwrap();
+    symlink("b/c", root.join("a/rel")).unwrap();
+    symlink(root.join("a/b"), root.join("abs")).unwrap();
+    symlink("../../../abs/file", root.join("a/b/c/up")).unwrap();
+    symlink("rel/../file", root.join("a/through")).unwrap();
+    symlink("missing", root.join("broken")).unwrap();
+    symlink("loop2", root.join("loop1")).unwrap();
+    symlink("loop1", root.join("loop2")).unwrap();
+
+    let ok_cases = [
+        "",
+        "a",
+        "a/./b//c/",
+        "a/b/..",
+        "a/rel",
+        "a/rel/..",
+        "abs/file",
+        "abs/c/../file",
+        "a/b/c/up",
+        "a/through",
+        "/",
+        "/..",
+    ];
+    for case in ok_cases {
+        let path = root.join(case);
+        assert_eq!(
+            canonicalize(&path).unwrap(),
+            fs::canonicalize(&path).unwrap(),
+            "{case}"
+        );
+    }
+
+    let err_cases = [
+        ("missing", libc::ENOENT),
+        ("broken", libc::ENOENT),
+        ("a/b/file/", libc::ENOTDIR),
+        ("a/b/file/..", libc::ENOTDIR),
+        ("loop1", libc::ELOOP),
+    ];
+    for (case, errno) in err_cases {
+        let err = canonicalize(&root.join(case)).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(errno), "{case}");
+    }
+
+    // Same as `fs::canonicalize`, rather than the current directory
+    let err = canonicalize("".as_ref()).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+    assert_eq!(err.kind(), fs::canonicalize("").unwrap_err().kind());
+}
+
+#[test]
+fn test_canonicalize_into_relative() {
+    let expected = fs::canonicalize("src").unwrap();
+    assert_eq!(canonicalize("src/../src/./".as_ref()).unwrap(), expected);
+    assert_eq!(
+        canonicalize(".".as_ref()).unwrap(),
+        fs::canonicalize(".").unwrap()
+    );
+}
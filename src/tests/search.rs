@@ -0,0 +1,45 @@
+use std::{fs, os::unix::fs::PermissionsExt};
+
+use crate::{search_roots, search_roots_with, FileStat};
+
+#[test]
+fn test_search_roots() {
+    let dir = tempfile::tempdir().unwrap();
+    let roots = ["first", "second", "third"].map(|root| dir.path().join(root));
+    for root in &roots {
+        fs::create_dir(root).unwrap();
+    }
+    fs::create_dir_all(roots[0].join("include/lib.h")).unwrap();
+    fs::create_dir_all(roots[1].join("include")).unwrap();
+    fs::write(roots[1].join("include/lib.h"), "").unwrap();
+    fs::create_dir_all(roots[2].join("bin")).unwrap();
+    fs::write(roots[2].join("bin/tool"), "").unwrap();
+
+    let (index, path) = search_roots(&roots, "include/lib.h").unwrap();
+    assert_eq!(index, 0);
+    assert_eq!(path, roots[0].join("include/lib.h"));
+
+    let (index, path) = search_roots_with(&roots, "include/lib.h", FileStat::is_file).unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(path, roots[1].join("include/lib.h"));
+
+    assert!(search_roots_with(&roots, "bin/tool", FileStat::is_executable).is_none());
+    fs::set_permissions(roots[2].join("bin/tool"), fs::Permissions::from_mode(0o755)).unwrap();
+    let (index, path) = search_roots_with(&roots, "bin/tool", FileStat::is_executable).unwrap();
+    assert_eq!(index, 2);
+    assert_eq!(path, roots[2].join("bin/tool"));
+
+    assert!(search_roots(&roots, "missing").is_none());
+    assert!(search_roots::<&str>(&[], "include/lib.h").is_none());
+}
+
+#[test]
+fn test_search_roots_absolute() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("file");
+    fs::write(&file, "").unwrap();
+
+    let (index, path) = search_roots(&["a", "b"], &file).unwrap();
+    assert_eq!(index, 0);
+    assert_eq!(path, file);
+}
@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use rand::{distributions::Uniform, prelude::Distribution};
+
+use crate::StackPathBuf;
+
+#[test]
+fn test_stack_path_push_pop() {
+    let mut path = StackPathBuf::<128>::new();
+    assert_eq!(path, Path::new(""));
+    path.push("a");
+    path.push("b/");
+    path.push("c");
+    assert_eq!(path, Path::new("a/b/c"));
+    assert!(path.pop());
+    assert_eq!(path, Path::new("a/b"));
+    path.push("/abs");
+    assert_eq!(path, Path::new("/abs"));
+    assert!(path.pop());
+    assert_eq!(path, Path::new("/"));
+    assert!(!path.pop());
+    path.clear();
+    assert_eq!(path, Path::new(""));
+}
+
+#[test]
+fn test_stack_path_spills_to_heap() {
+    let mut path = StackPathBuf::<8>::from_path("abc");
+    assert!(path.is_inline());
+    path.push("defg");
+    assert!(!path.is_inline());
+    assert_eq!(path, Path::new("abc/defg"));
+    path.pop();
+    assert_eq!(path, Path::new("abc"));
+    assert_eq!(path.as_c_str().unwrap().to_bytes(), b"abc");
+    assert_eq!(PathBuf::from(path), PathBuf::from("abc"));
+}
+
+#[test]
+fn test_stack_path_stat() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut path = StackPathBuf::<128>::from_path(dir.path());
+    assert!(path.stat().unwrap().is_dir());
+    path.push("file");
+    assert!(!path.exists());
+    std::fs::write(&*path, b"hello").unwrap();
+    let stat = path.stat().unwrap();
+    assert!(stat.is_file());
+    assert_eq!(stat.len(), 5);
+
+    let nul = StackPathBuf::<128>::from_path(std::ffi::OsStr::new("a\0b"));
+    assert_eq!(
+        nul.stat().unwrap_err().kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn test_stack_path_fuzz() {
+    let mut rng = rand::thread_rng();
+    const SAMPLES: usize = 20_000;
+
+    let options = ["a", "bc", "/", "/", "defgh", ".."];
+    let ops = Uniform::from(0..options.len() + 2);
+
+    for _ in 0..SAMPLES {
+        let mut path = StackPathBuf::<16>::new();
+        let mut expected = PathBuf::new();
+        for _ in 0..12 {
+            match ops.sample(&mut rng) {
+                i if i < options.len() => {
+                    path.push(options[i]);
+                    expected.push(options[i]);
+                }
+                i if i == options.len() => assert_eq!(path.pop(), expected.pop()),
+                _ => {
+                    path.clear();
+                    expected.clear();
+                }
+            }
+            assert_eq!(path, expected);
+            assert_eq!(path.as_c_str().unwrap().to_bytes(), path.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::{mem::MaybeUninit, path::Path};
+
+use crate::{PathTemplate, RenderError, TemplateError};
+
+fn render(template: &str, values: &[(&str, &str)]) -> Result<String, RenderError> {
+    let values: Vec<(&str, &Path)> = values.iter().map(|&(k, v)| (k, Path::new(v))).collect();
+    let template = PathTemplate::new(template).unwrap();
+    let path = template.render(&values)?;
+    Ok(path.to_str().unwrap().to_owned())
+}
+
+#[test]
+fn test_template_render() {
+    let values = [("root", "/srv"), ("project", "crate"), ("build_id", "42")];
+    assert_eq!(
+        render("{root}/projects/{project}/builds/{build_id}.log", &values).unwrap(),
+        "/srv/projects/crate/builds/42.log"
+    );
+    assert_eq!(
+        render("/data/{name}", &[("name", "a/b")]).unwrap(),
+        "/data/a/b"
+    );
+    assert_eq!(
+        render("{{{name}}}.txt", &[("name", "x")]).unwrap(),
+        "{x}.txt"
+    );
+    assert_eq!(render("{a}-{a}", &[("a", "x")]).unwrap(), "x-x");
+    assert_eq!(render("plain/path", &[]).unwrap(), "plain/path");
+}
+
+#[test]
+fn test_template_absolute_reset() {
+    // Same as joining the segments with `join_in_buff`
+    assert_eq!(
+        render("cache/{dir}/file", &[("dir", "/tmp")]).unwrap(),
+        "/tmp/file"
+    );
+    assert_eq!(render("/{root}/x", &[("root", "/srv")]).unwrap(), "/srv/x");
+    assert_eq!(render("/{root}/x", &[("root", "srv")]).unwrap(), "/srv/x");
+    assert_eq!(render("{root}/x", &[("root", "srv/")]).unwrap(), "srv/x");
+    // Only at the start of a segment
+    assert_eq!(render("a/b{c}", &[("c", "/d")]).unwrap(), "a/b/d");
+    assert_eq!(render("a/{c}/b", &[("c", "")]).unwrap(), "a/b");
+    assert_eq!(render("{c}/b", &[("c", "")]).unwrap(), "b");
+}
+
+#[test]
+fn test_template_render_errors() {
+    assert_eq!(
+        render("{root}/{name}", &[("root", "/srv")]),
+        Err(RenderError::Missing("name".to_owned()))
+    );
+    assert_eq!(
+        render("{root}", &[("root", "/srv"), ("extra", "x")]),
+        Err(RenderError::Unused("extra".to_owned()))
+    );
+}
+
+#[test]
+fn test_template_parse_errors() {
+    let err = |template| PathTemplate::new(template).unwrap_err();
+    assert_eq!(err("{root"), TemplateError::UnclosedPlaceholder);
+    assert_eq!(err("root}"), TemplateError::UnmatchedBrace);
+    assert_eq!(err("{}"), TemplateError::InvalidName("".to_owned()));
+    assert_eq!(err("{1st}"), TemplateError::InvalidName("1st".to_owned()));
+    assert_eq!(err("{a b}"), TemplateError::InvalidName("a b".to_owned()));
+    assert_eq!(
+        err("{a}{b}"),
+        TemplateError::AdjacentPlaceholders("a".to_owned(), "b".to_owned())
+    );
+    assert_eq!(err("a//b"), TemplateError::EmptySegment);
+    assert_eq!(err("a/"), TemplateError::EmptySegment);
+    assert_eq!(err("//a"), TemplateError::EmptySegment);
+
+    let template = PathTemplate::new("{a}/{b_2}.{a}").unwrap();
+    assert_eq!(
+        template.placeholders().collect::<Vec<_>>(),
+        ["a", "b_2", "a"]
+    );
+}
+
+#[test]
+fn test_template_render_in_buff() {
+    let template = PathTemplate::new("{root}/{name}").unwrap();
+    let name = "n".repeat(200);
+
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    let path = template
+        .render_in_buff(
+            &mut raw_buff,
+            &mut path_buff,
+            &[("root", "/srv".as_ref()), ("name", "short".as_ref())],
+        )
+        .unwrap();
+    assert_eq!(path, Path::new("/srv/short"));
+
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut path_buff = None;
+    let path = template
+        .render_in_buff(
+            &mut raw_buff,
+            &mut path_buff,
+            &[("root", "/srv".as_ref()), ("name", name.as_ref())],
+        )
+        .unwrap();
+    assert_eq!(path, Path::new("/srv").join(&name));
+}
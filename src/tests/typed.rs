@@ -0,0 +1,74 @@
+use std::{error::Error, path::Path};
+
+use crate::{with_typed_paths, AbsPath, DirPath, FilePath, RelPath, TypedPathError};
+
+#[test]
+fn test_typed_checked_constructors() {
+    assert_eq!(AbsPath::new("/srv").unwrap(), Path::new("/srv"));
+    assert!(matches!(
+        AbsPath::new("srv"),
+        Err(TypedPathError::NotAbsolute)
+    ));
+    assert_eq!(RelPath::new("logs/a").unwrap(), Path::new("logs/a"));
+    assert_eq!(RelPath::new("").unwrap(), Path::new(""));
+    assert!(matches!(
+        RelPath::new("/etc"),
+        Err(TypedPathError::NotRelative)
+    ));
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("file");
+    std::fs::write(&file, "").unwrap();
+
+    let checked = DirPath::new(dir.path()).unwrap();
+    assert_eq!(checked.as_path(), dir.path());
+    let _: &AbsPath = checked;
+    assert!(matches!(
+        DirPath::new(&file),
+        Err(TypedPathError::NotDirectory)
+    ));
+    assert!(matches!(
+        DirPath::new("tmp"),
+        Err(TypedPathError::NotAbsolute)
+    ));
+
+    assert_eq!(FilePath::new(&file).unwrap(), file.as_path());
+    assert!(matches!(
+        FilePath::new(dir.path()),
+        Err(TypedPathError::NotFile)
+    ));
+    let err = FilePath::new(&dir.path().join("missing")).unwrap_err();
+    assert!(matches!(&err, TypedPathError::Io(err) if err.kind() == std::io::ErrorKind::NotFound));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn test_typed_joins() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = DirPath::new(dir.path()).unwrap();
+    let abs = AbsPath::new("/srv/app").unwrap();
+    let logs = RelPath::new("logs/").unwrap();
+    let name = RelPath::new("server.log").unwrap();
+    let empty = RelPath::new("").unwrap();
+
+    with_typed_paths! {
+        in_root = root / logs / name,
+        in_abs = abs / empty / name,
+        relative = logs / name,
+        alone = abs
+    };
+    let in_root: &AbsPath = in_root;
+    let relative: &RelPath = relative;
+    assert_eq!(in_root, dir.path().join("logs/server.log").as_path());
+    assert_eq!(in_abs, Path::new("/srv/app/server.log"));
+    assert_eq!(relative, Path::new("logs/server.log"));
+    assert_eq!(alone, Path::new("/srv/app"));
+
+    let long = "l".repeat(200);
+    let long = RelPath::new(&long).unwrap();
+    let len = with_typed_paths! {
+        path = abs / long / name
+        => path.as_os_str().len()
+    };
+    assert_eq!(len, "/srv/app/".len() + 200 + "/server.log".len());
+}
@@ -0,0 +1,72 @@
+use std::{mem::MaybeUninit, path::PathBuf};
+
+use rand::{distributions::Uniform, prelude::Distribution};
+
+use crate::{join_in_buff_utf8, with_utf8_paths};
+
+#[test]
+fn test_utf8_join() {
+    let p1 = "hello";
+    let p2 = String::from("wörld");
+    let p3 = "some/other/path";
+    let result = with_utf8_paths! {
+        path = p1 / p2 / p3 => path.to_owned()
+    };
+    assert_eq!(result, "hello/wörld/some/other/path");
+}
+
+#[test]
+fn test_utf8_absolute() {
+    let p1 = "hello";
+    let p2 = "/world";
+    with_utf8_paths! {
+        path = p1 / p2
+    };
+    assert_eq!(path, "/world");
+}
+
+#[test]
+fn test_utf8_overflow() {
+    let p1 = "Call me Ishmael. Some years ago—never mind how long precisely—having little or no money in my purse";
+    let p2 = "and nothing particular to interest me on shore";
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut str_buff = None;
+    let path = join_in_buff_utf8(&mut raw_buff, &mut str_buff, [p1, p2]);
+    assert_eq!(path, format!("{p1}/{p2}"));
+    assert!(str_buff.is_some());
+}
+
+/// Joining UTF-8 paths should produce exactly the same string as `PathBuf`
+#[test]
+fn test_utf8_fuzz() {
+    let mut rng = rand::thread_rng();
+    const SAMPLES: usize = 20_000;
+
+    let options = ['a', 'é', '/', '.', '字'];
+    let length_dist = Uniform::from(0..30);
+    let opt_dist = Uniform::from(0..options.len());
+
+    let segment = |rng: &mut rand::rngs::ThreadRng| -> String {
+        let len = length_dist.sample(rng);
+        opt_dist
+            .sample_iter(rng)
+            .take(len)
+            .map(|i| options[i])
+            .collect()
+    };
+
+    for _ in 0..SAMPLES {
+        let p1 = segment(&mut rng);
+        let p2 = segment(&mut rng);
+        let p3 = segment(&mut rng);
+
+        let mut expected = PathBuf::new();
+        for p in [&p1, &p2, &p3] {
+            expected.push(p);
+        }
+
+        with_utf8_paths! {
+            path = p1 / p2 / p3 => assert_eq!(Some(path), expected.to_str())
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fs, io,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use crate::{walk, walk_with, FileType, WalkEntry, WalkOptions};
+
+/// Builds:
+///
+/// ```text
+/// root/
+///   a/
+///     b/
+///       deep.txt
+///     a.txt
+///   c/
+///     skip/
+///       hidden.txt
+///   top.txt
+/// ```
+fn tree() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("a/b")).unwrap();
+    fs::create_dir_all(root.join("c/skip")).unwrap();
+    for file in ["a/b/deep.txt", "a/a.txt", "c/skip/hidden.txt", "top.txt"] {
+        fs::write(root.join(file), "").unwrap();
+    }
+    tmp
+}
+
+fn collect<P: FnMut(&WalkEntry<'_>) -> bool>(
+    root: &Path,
+    options: &WalkOptions,
+    prune: P,
+) -> Vec<(PathBuf, usize, FileType)> {
+    let mut walker = walk_with(root, options, prune).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = walker.next() {
+        let entry = entry.unwrap();
+        assert_eq!(
+            entry.c_path().to_bytes(),
+            entry.path().as_os_str().as_encoded_bytes()
+        );
+        let relative = entry.path().strip_prefix(root).unwrap().to_owned();
+        entries.push((relative, entry.depth(), entry.file_type()));
+    }
+    entries
+}
+
+fn paths(entries: &[(PathBuf, usize, FileType)]) -> Vec<&str> {
+    entries
+        .iter()
+        .map(|(p, _, _)| p.to_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_walk_sorted() {
+    let tmp = tree();
+    let options = WalkOptions {
+        sort: true,
+        ..WalkOptions::default()
+    };
+    let entries = collect(tmp.path(), &options, |_| false);
+    assert_eq!(
+        paths(&entries),
+        [
+            "",
+            "a",
+            "a/a.txt",
+            "a/b",
+            "a/b/deep.txt",
+            "c",
+            "c/skip",
+            "c/skip/hidden.txt",
+            "top.txt"
+        ]
+    );
+    let depths: Vec<_> = entries.iter().map(|(_, d, _)| *d).collect();
+    assert_eq!(depths, [0, 1, 2, 2, 3, 1, 2, 3, 1]);
+    assert_eq!(entries[1].2, FileType::Dir);
+    assert_eq!(entries[2].2, FileType::File);
+}
+
+#[test]
+fn test_walk_unsorted() {
+    let tmp = tree();
+    let entries = collect(tmp.path(), &WalkOptions::default(), |_| false);
+    // Pre-order: every entry comes after its parent
+    for (i, (path, _, _)) in entries.iter().enumerate().skip(1) {
+        let parent = path.parent().unwrap();
+        assert!(entries[..i].iter().any(|(p, _, _)| p == parent), "{path:?}");
+    }
+    assert_eq!(entries.len(), 9);
+}
+
+#[test]
+fn test_walk_max_depth_and_prune() {
+    let tmp = tree();
+    let options = WalkOptions {
+        max_depth: 1,
+        sort: true,
+        ..WalkOptions::default()
+    };
+    let entries = collect(tmp.path(), &options, |_| false);
+    assert_eq!(paths(&entries), ["", "a", "c", "top.txt"]);
+
+    let options = WalkOptions {
+        max_depth: 0,
+        ..WalkOptions::default()
+    };
+    assert_eq!(paths(&collect(tmp.path(), &options, |_| false)), [""]);
+
+    let options = WalkOptions {
+        sort: true,
+        ..WalkOptions::default()
+    };
+    let entries = collect(tmp.path(), &options, |entry| {
+        entry.file_name() == "skip" || entry.file_name() == "a.txt"
+    });
+    assert_eq!(
+        paths(&entries),
+        ["", "a", "a/b", "a/b/deep.txt", "c", "top.txt"]
+    );
+
+    assert!(collect(tmp.path(), &options, |entry| entry.depth() == 0).is_empty());
+}
+
+#[test]
+fn test_walk_links() {
+    let tmp = tree();
+    let root = tmp.path();
+    symlink("a", root.join("link")).unwrap();
+    symlink(".", root.join("a/b/loop")).unwrap();
+    symlink("missing", root.join("broken")).unwrap();
+
+    let options = WalkOptions {
+        sort: true,
+        ..WalkOptions::default()
+    };
+    let entries = collect(root, &options, |_| false);
+    let link = entries
+        .iter()
+        .find(|(p, _, _)| p == Path::new("link"))
+        .unwrap();
+    assert_eq!(link.2, FileType::Symlink);
+    assert!(!entries
+        .iter()
+        .any(|(p, _, _)| p.starts_with("link") && p != Path::new("link")));
+
+    let options = WalkOptions {
+        follow_links: true,
+        sort: true,
+        ..WalkOptions::default()
+    };
+    let mut walker = walk(root, &options).unwrap();
+    let mut found = Vec::new();
+    let mut loops = 0;
+    while let Some(entry) = walker.next() {
+        match entry {
+            Ok(entry) => found.push((
+                entry.path().strip_prefix(root).unwrap().to_owned(),
+                entry.file_type(),
+            )),
+            Err(err) => {
+                assert_eq!(err.raw_os_error(), Some(libc::ELOOP));
+                loops += 1;
+            }
+        }
+    }
+    // a/b/loop, and link/b/loop
+    assert_eq!(loops, 2);
+    assert!(found.contains(&("link".into(), FileType::Dir)));
+    assert!(found.contains(&("link/b/deep.txt".into(), FileType::File)));
+    assert!(found.contains(&("broken".into(), FileType::Symlink)));
+}
+
+#[test]
+fn test_walk_long_paths() {
+    // Paths that outgrow the inline buffer, several levels deep
+    let tmp = tempfile::tempdir().unwrap();
+    let name = "d".repeat(60);
+    let mut dir = tmp.path().to_owned();
+    for _ in 0..5 {
+        dir.push(&name);
+    }
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("file"), "").unwrap();
+
+    let mut walker = walk(tmp.path(), &WalkOptions::default()).unwrap();
+    let mut last = None;
+    while let Some(entry) = walker.next() {
+        last = Some(entry.unwrap().path().to_owned());
+    }
+    assert_eq!(last, Some(dir.join("file")));
+}
+
+#[test]
+fn test_walk_root_file_name() {
+    let tmp = tree();
+    let root = tmp.path().join("a");
+    let root = root.to_str().unwrap();
+    let root_name = |root: &str| {
+        let mut walker = walk(root, &WalkOptions::default()).unwrap();
+        let entry = walker.next().unwrap().unwrap();
+        entry.file_name().to_str().unwrap().to_owned()
+    };
+    assert_eq!(root_name(root), "a");
+    assert_eq!(root_name(&format!("{root}/")), "a");
+    assert_eq!(root_name(&format!("{root}//")), "a");
+    assert_eq!(root_name(&format!("{root}/.")), format!("{root}/."));
+    assert_eq!(root_name("/"), "/");
+
+    // Entries below a root with trailing separators are unaffected
+    let mut walker = walk(format!("{root}//"), &WalkOptions::default()).unwrap();
+    walker.next();
+    let entry = walker.next().unwrap().unwrap();
+    assert!(["b", "a.txt"].contains(&entry.file_name().to_str().unwrap()));
+}
+
+#[test]
+fn test_walk_errors() {
+    let tmp = tree();
+    let err = walk(tmp.path().join("top.txt"), &WalkOptions::default()).err();
+    assert_eq!(err.unwrap().kind(), io::ErrorKind::NotADirectory);
+    assert!(walk(tmp.path().join("missing"), &WalkOptions::default()).is_err());
+}
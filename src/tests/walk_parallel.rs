@@ -0,0 +1,192 @@
+use std::{
+    ffi::CString,
+    fs,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::fs::symlink,
+    },
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{walk, walk_parallel, FileType, WalkOptions};
+
+fn big_tree() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    for a in 0..8 {
+        for b in 0..8 {
+            let dir = tmp.path().join(format!("{a}/{b}"));
+            fs::create_dir_all(&dir).unwrap();
+            for c in 0..5 {
+                fs::write(dir.join(format!("file-{c}")), "").unwrap();
+            }
+        }
+    }
+    tmp
+}
+
+fn walk_serial(root: &Path, options: &WalkOptions) -> Vec<(PathBuf, usize, FileType)> {
+    let mut walker = walk(root, options).unwrap();
+    let mut entries = Vec::new();
+    while let Some(entry) = walker.next() {
+        let entry = entry.unwrap();
+        entries.push((entry.path().to_owned(), entry.depth(), entry.file_type()));
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+fn walk_threads(
+    root: &Path,
+    options: &WalkOptions,
+    threads: usize,
+    descend: impl Fn(&Path) -> bool + Sync,
+) -> Vec<(PathBuf, usize, FileType)> {
+    let entries = Mutex::new(Vec::new());
+    walk_parallel(root, options, threads, |entry| {
+        let entry = entry.unwrap();
+        entries
+            .lock()
+            .unwrap()
+            .push((entry.path().to_owned(), entry.depth(), entry.file_type()));
+        descend(entry.path())
+    })
+    .unwrap();
+    let mut entries = entries.into_inner().unwrap();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+#[test]
+fn test_walk_parallel_matches_serial() {
+    let tmp = big_tree();
+    let options = WalkOptions::default();
+    let expected = walk_serial(tmp.path(), &options);
+    assert_eq!(expected.len(), 1 + 8 + 64 + 64 * 5);
+    for threads in [0, 1, 2, 8] {
+        assert_eq!(
+            walk_threads(tmp.path(), &options, threads, |_| true),
+            expected,
+            "{threads} threads"
+        );
+    }
+
+    let options = WalkOptions {
+        max_depth: 2,
+        sort: true,
+        ..WalkOptions::default()
+    };
+    assert_eq!(
+        walk_threads(tmp.path(), &options, 4, |_| true),
+        walk_serial(tmp.path(), &options)
+    );
+}
+
+/// Makes `depth` nested directories with long names under `dir`, each one
+/// relative to the last, so that the full path can grow past `PATH_MAX`.
+fn deep_chain(dir: &Path, depth: usize) {
+    let name = CString::new("d".repeat(200)).unwrap();
+    let mut fd = OwnedFd::from(fs::File::open(dir).unwrap());
+    for _ in 0..depth {
+        assert_eq!(
+            unsafe { libc::mkdirat(fd.as_raw_fd(), name.as_ptr(), 0o755) },
+            0
+        );
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        let next = unsafe { libc::openat(fd.as_raw_fd(), name.as_ptr(), flags) };
+        assert!(next >= 0);
+        fd = unsafe { OwnedFd::from_raw_fd(next) };
+    }
+}
+
+#[test]
+fn test_walk_parallel_wide_and_deep() {
+    // More siblings than can be queued with their own fd, each too deep to
+    // open by its full path
+    let tmp = tempfile::tempdir().unwrap();
+    for i in 0..100 {
+        let dir = tmp.path().join(i.to_string());
+        fs::create_dir(&dir).unwrap();
+        deep_chain(&dir, 25);
+    }
+
+    let options = WalkOptions::default();
+    let expected = walk_serial(tmp.path(), &options);
+    assert_eq!(expected.len(), 1 + 100 + 100 * 25);
+    let longest = expected.iter().map(|(p, _, _)| p.as_os_str().len()).max();
+    assert!(longest.unwrap() > libc::PATH_MAX as usize);
+    for threads in [1, 4] {
+        assert_eq!(
+            walk_threads(tmp.path(), &options, threads, |_| true),
+            expected,
+            "{threads} threads"
+        );
+    }
+}
+
+#[test]
+fn test_walk_parallel_prune() {
+    let tmp = big_tree();
+    let pruned = tmp.path().join("3");
+    let entries = walk_threads(tmp.path(), &WalkOptions::default(), 4, |path| {
+        path != pruned
+    });
+    assert!(entries.iter().any(|(p, _, _)| *p == pruned));
+    assert!(!entries
+        .iter()
+        .any(|(p, _, _)| p.starts_with(&pruned) && *p != pruned));
+    assert_eq!(entries.len(), 1 + 8 + 56 + 56 * 5);
+
+    let only_root = walk_threads(tmp.path(), &WalkOptions::default(), 4, |_| false);
+    assert_eq!(only_root.len(), 1);
+}
+
+#[test]
+fn test_walk_parallel_links() {
+    let tmp = big_tree();
+    symlink("..", tmp.path().join("0/0/up")).unwrap();
+    let options = WalkOptions {
+        follow_links: true,
+        ..WalkOptions::default()
+    };
+    let loops = Mutex::new(0);
+    walk_parallel(tmp.path(), &options, 4, |entry| {
+        if let Err(err) = entry {
+            assert_eq!(err.raw_os_error(), Some(libc::ELOOP));
+            *loops.lock().unwrap() += 1;
+        }
+        true
+    })
+    .unwrap();
+    assert_eq!(loops.into_inner().unwrap(), 1);
+}
+
+#[test]
+fn test_walk_parallel_errors() {
+    let tmp = big_tree();
+    let file = tmp.path().join("0/0/file-0");
+    assert!(walk_parallel(&file, &WalkOptions::default(), 2, |_| true).is_err());
+}
+
+#[test]
+fn test_walk_parallel_root_file_name() {
+    let tmp = big_tree();
+    let root = format!("{}//", tmp.path().join("0").display());
+    let names = Mutex::new(Vec::new());
+    let options = WalkOptions {
+        max_depth: 1,
+        ..WalkOptions::default()
+    };
+    walk_parallel(&root, &options, 2, |entry| {
+        let entry = entry.unwrap();
+        let name = entry.file_name().to_str().unwrap().to_owned();
+        names.lock().unwrap().push((entry.depth(), name));
+        true
+    })
+    .unwrap();
+    let mut names = names.into_inner().unwrap();
+    names.sort();
+    assert_eq!(names[0], (0, "0".to_owned()));
+    assert_eq!(names[1], (1, "0".to_owned()));
+    assert_eq!(names.len(), 9);
+}
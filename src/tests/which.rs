@@ -0,0 +1,64 @@
+use std::{ffi::OsString, fs, os::unix::fs::PermissionsExt, path::Path};
+
+use crate::{which, which_all, which_all_in, which_in};
+
+fn write_file(path: &Path, mode: u32) {
+    fs::write(path, "").unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[test]
+fn test_which_in() {
+    let dir = tempfile::tempdir().unwrap();
+    let dirs = ["a", "b", "c"].map(|d| dir.path().join(d));
+    for d in &dirs {
+        fs::create_dir(d).unwrap();
+    }
+    write_file(&dirs[0].join("tool"), 0o644);
+    fs::create_dir(dirs[1].join("tool")).unwrap();
+    write_file(&dirs[2].join("tool"), 0o755);
+    write_file(&dirs[0].join("other"), 0o700);
+    write_file(&dirs[2].join("other"), 0o711);
+
+    let mut list = OsString::new();
+    for (i, d) in dirs.iter().enumerate() {
+        if i > 0 {
+            list.push(":");
+        }
+        list.push(d);
+    }
+
+    assert_eq!(which_in("tool", &list).unwrap(), dirs[2].join("tool"));
+    assert_eq!(which_in("other", &list).unwrap(), dirs[0].join("other"));
+    assert!(which_in("missing", &list).is_none());
+    assert!(which_in("", &list).is_none());
+    assert!(which_in("tool", "").is_none());
+
+    let all: Vec<_> = which_all_in("other", &list).collect();
+    assert_eq!(all, [dirs[0].join("other"), dirs[2].join("other")]);
+
+    // Names with a slash aren't searched for
+    let direct = dirs[2].join("tool");
+    assert_eq!(which_in(&direct, "").unwrap(), direct);
+    assert!(which_in("c/tool", &list).is_none());
+    assert!(which_in(dirs[0].join("tool"), &list).is_none());
+}
+
+#[test]
+fn test_which_skips_missing_entries() {
+    let exe = std::env::current_exe().unwrap();
+    let name = exe.file_name().unwrap();
+    let list = format!(
+        "/path-no-alloc-missing::{}:",
+        exe.parent().unwrap().display()
+    );
+    assert_eq!(which_in(name, &list).unwrap(), exe);
+    assert_eq!(which_all_in(name, &list).count(), 1);
+}
+
+#[test]
+fn test_which_path() {
+    assert!(which("sh").is_some());
+    let name = String::from("sh");
+    assert_eq!(which_all(name).next(), which("sh"));
+}
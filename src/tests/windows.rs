@@ -0,0 +1,158 @@
+use std::mem::MaybeUninit;
+
+use crate::{
+    join_windows_in_buff, normalize_windows_in_buff, with_windows_paths, WindowsComponent,
+    WindowsPath, WindowsPrefix,
+};
+
+fn join<const N: usize>(paths: [&str; N]) -> String {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut bytes_buff = None;
+    let path = join_windows_in_buff(&mut raw_buff, &mut bytes_buff, paths.map(str::as_bytes));
+    path.to_str().unwrap().to_owned()
+}
+
+fn normalize(path: &str) -> String {
+    let mut raw_buff = [MaybeUninit::uninit(); 128];
+    let mut bytes_buff = None;
+    let path = normalize_windows_in_buff(&mut raw_buff, &mut bytes_buff, path.as_bytes());
+    path.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn test_windows_prefix() {
+    let cases: [(&str, Option<WindowsPrefix>); 14] = [
+        (r"C:\foo", Some(WindowsPrefix::Disk(b'C'))),
+        (r"c:foo", Some(WindowsPrefix::Disk(b'C'))),
+        (
+            r"\\server\share\foo",
+            Some(WindowsPrefix::UNC(b"server", b"share")),
+        ),
+        // `/` works as a separator, except in verbatim prefixes
+        (
+            r"//server/share",
+            Some(WindowsPrefix::UNC(b"server", b"share")),
+        ),
+        (
+            r"\\server/share\foo",
+            Some(WindowsPrefix::UNC(b"server", b"share")),
+        ),
+        (r"//./COM1", Some(WindowsPrefix::DeviceNS(b"COM1"))),
+        (r"//?/C:/foo", Some(WindowsPrefix::UNC(b"?", b"C:"))),
+        (r"\\?\C:/foo", Some(WindowsPrefix::Verbatim(b"C:/foo"))),
+        (r"\\?\C:\foo", Some(WindowsPrefix::VerbatimDisk(b'C'))),
+        (
+            r"\\?\UNC\server\share\foo",
+            Some(WindowsPrefix::VerbatimUNC(b"server", b"share")),
+        ),
+        (
+            r"\\?\pictures\foo",
+            Some(WindowsPrefix::Verbatim(b"pictures")),
+        ),
+        (r"\\.\COM42", Some(WindowsPrefix::DeviceNS(b"COM42"))),
+        (r"\\server", None),
+        (r"\foo\bar", None),
+    ];
+    for (path, prefix) in cases {
+        assert_eq!(WindowsPath::new(path).prefix(), prefix, "{path}");
+    }
+}
+
+#[test]
+fn test_windows_absolute() {
+    let cases = [
+        (r"C:\foo", true, true),
+        (r"C:foo", false, false),
+        (r"\foo", false, true),
+        (r"foo\bar", false, false),
+        (r"\\server\share", true, true),
+        (r"\\?\C:", true, true),
+        (r"\\.\COM1", true, true),
+        ("//server/share", true, true),
+        ("C:/foo", true, true),
+    ];
+    for (path, absolute, has_root) in cases {
+        let path = WindowsPath::new(path);
+        assert_eq!(path.is_absolute(), absolute, "{path}");
+        assert_eq!(path.has_root(), has_root, "{path}");
+    }
+}
+
+#[test]
+fn test_windows_components() {
+    let path = WindowsPath::new(r"C:\Users\.\alecto/..//file.txt");
+    let components: Vec<_> = path.components().collect();
+    assert_eq!(
+        components,
+        [
+            WindowsComponent::Prefix(WindowsPrefix::Disk(b'C')),
+            WindowsComponent::RootDir,
+            WindowsComponent::Normal(b"Users"),
+            WindowsComponent::Normal(b"alecto"),
+            WindowsComponent::ParentDir,
+            WindowsComponent::Normal(b"file.txt"),
+        ]
+    );
+    assert_eq!(path.file_name(), Some(&b"file.txt"[..]));
+
+    // Forward slashes aren't separators in verbatim paths
+    let path = WindowsPath::new(r"\\?\C:\a/b\.");
+    let components: Vec<_> = path.components().collect();
+    assert_eq!(
+        components,
+        [
+            WindowsComponent::Prefix(WindowsPrefix::VerbatimDisk(b'C')),
+            WindowsComponent::RootDir,
+            WindowsComponent::Normal(b"a/b"),
+            WindowsComponent::CurDir,
+        ]
+    );
+}
+
+#[test]
+fn test_windows_join() {
+    assert_eq!(
+        join([r"C:\Users", "alecto", "file.txt"]),
+        r"C:\Users\alecto\file.txt"
+    );
+    assert_eq!(join([r"C:\Users\", "alecto"]), r"C:\Users\alecto");
+    assert_eq!(join(["C:", "foo"]), "C:foo");
+    assert_eq!(join([r"C:\Users", r"\Windows"]), r"C:\Windows");
+    assert_eq!(join([r"\\server\share\a", r"\b"]), r"\\server\share\b");
+    assert_eq!(join([r"C:\Users", r"D:\data"]), r"D:\data");
+    assert_eq!(join([r"C:\Users", "D:data"]), "D:data");
+    assert_eq!(join([r"foo", r"\\?\C:\bar"]), r"\\?\C:\bar");
+    assert_eq!(join(["a/b", "c"]), r"a/b\c");
+    assert_eq!(join(["", "a"]), "a");
+}
+
+#[test]
+fn test_windows_join_verbatim() {
+    assert_eq!(join([r"\\?\C:\a", r"b\.\c\..\d"]), r"\\?\C:\a\b\d");
+    assert_eq!(join([r"\\?\C:\a", r"..\.."]), r"\\?\C:\");
+    assert_eq!(join([r"\\?\C:\a\b", r"\c"]), r"\\?\C:\c");
+    assert_eq!(join([r"\\?\C:", "a"]), r"\\?\C:\a");
+}
+
+#[test]
+fn test_windows_join_macro_overflow() {
+    let p1 = r"C:\Call me Ishmael. Some years ago - never mind how long precisely - having little";
+    let p2 = "or no money in my purse, and nothing particular to interest me on shore";
+    with_windows_paths! {
+        path = p1 / p2
+    };
+    assert_eq!(path.to_str(), Some(format!(r"{p1}\{p2}").as_str()));
+}
+
+#[test]
+fn test_windows_normalize() {
+    assert_eq!(normalize(r"C:\a\.\b\..\c"), r"C:\a\c");
+    assert_eq!(normalize(r"C:/a//b/"), r"C:\a\b");
+    assert_eq!(normalize(r"C:\..\a"), r"C:\a");
+    assert_eq!(normalize(r"C:..\a"), r"C:..\a");
+    assert_eq!(normalize(r"..\..\a\..\b"), r"..\..\b");
+    assert_eq!(normalize(r"a\.."), "");
+    assert_eq!(normalize(r"\\server\share\a\..\b"), r"\\server\share\b");
+    assert_eq!(normalize(r"\\?\C:\a\..\b"), r"\\?\C:\a\..\b");
+    assert_eq!(normalize(r"\a/b"), r"\a\b");
+}
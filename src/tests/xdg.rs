@@ -0,0 +1,95 @@
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::xdg::{self, BaseDir};
+
+#[test]
+fn test_xdg_place() {
+    let env = |name: &OsStr| -> Option<OsString> {
+        match name.to_str()? {
+            "HOME" => Some("/home/me".into()),
+            "XDG_CACHE_HOME" => Some("/var/cache/me".into()),
+            "XDG_STATE_HOME" => Some("relative/state".into()),
+            _ => None,
+        }
+    };
+    let cases = [
+        (BaseDir::Config, Some("/home/me/.config/app/config.toml")),
+        (BaseDir::Data, Some("/home/me/.local/share/app/config.toml")),
+        (BaseDir::Cache, Some("/var/cache/me/app/config.toml")),
+        (
+            BaseDir::State,
+            Some("/home/me/.local/state/app/config.toml"),
+        ),
+        (BaseDir::Runtime, None),
+    ];
+    for (base, expected) in cases {
+        assert_eq!(
+            xdg::place_with(base, "app/config.toml", env).map(|p| p.into_path_buf()),
+            expected.map(Into::into),
+            "{base:?}"
+        );
+    }
+    assert!(xdg::place_with(BaseDir::Config, "app", |_| None).is_none());
+}
+
+#[test]
+fn test_xdg_find() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for sub in ["home/.config/app", "sys1/app", "sys2/app"] {
+        fs::create_dir_all(root.join(sub)).unwrap();
+    }
+    fs::write(root.join("home/.config/app/user.toml"), "").unwrap();
+    fs::write(root.join("sys1/app/user.toml"), "").unwrap();
+    fs::write(root.join("sys2/app/system.toml"), "").unwrap();
+
+    let mut dirs = OsString::from("relative/dir::");
+    dirs.push(root.join("sys1"));
+    dirs.push(":");
+    dirs.push(root.join("sys2"));
+    let env = |name: &OsStr| -> Option<OsString> {
+        match name.to_str()? {
+            "HOME" => Some(root.join("home").into()),
+            "XDG_CONFIG_DIRS" => Some(dirs.clone()),
+            _ => None,
+        }
+    };
+
+    let find = |relative: &str| xdg::find_with(BaseDir::Config, relative, env);
+    assert_eq!(
+        find("app/user.toml").unwrap(),
+        root.join("home/.config/app/user.toml")
+    );
+    assert_eq!(
+        find("app/system.toml").unwrap(),
+        root.join("sys2/app/system.toml")
+    );
+    assert!(find("app/missing.toml").is_none());
+    assert!(xdg::find_with(BaseDir::Cache, "app/system.toml", env).is_none());
+
+    // Without XDG_CONFIG_DIRS, only the default /etc/xdg is searched
+    let found = xdg::find_with(BaseDir::Config, "app/system.toml", |name| {
+        (name == "HOME").then(|| root.join("home").into())
+    });
+    assert!(found.is_none());
+}
+
+#[test]
+fn test_xdg_env() {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute());
+    let home = env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(|home| Path::new(&home).join(".config"));
+    let expected = config_home.or(home).map(|dir| dir.join("app"));
+    assert_eq!(
+        xdg::place(BaseDir::Config, "app").map(|p| p.into_path_buf()),
+        expected
+    );
+}
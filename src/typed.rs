@@ -0,0 +1,309 @@
+use std::{error, fmt, io, marker::PhantomData, ops::Deref, path::Path};
+
+use crate::StackPathBuf;
+
+/// The reason a path couldn't be checked into one of the typed path
+/// markers.
+#[derive(Debug)]
+pub enum TypedPathError {
+    /// [`AbsPath`], [`DirPath`] and [`FilePath`] have to be absolute.
+    NotAbsolute,
+    /// [`RelPath`] can't be absolute.
+    NotRelative,
+    /// [`DirPath`] has to name a directory.
+    NotDirectory,
+    /// [`FilePath`] has to name a regular file.
+    NotFile,
+    /// Checking what a [`DirPath`] or [`FilePath`] names failed, for example
+    /// because it doesn't exist.
+    Io(io::Error),
+}
+
+impl fmt::Display for TypedPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypedPathError::NotAbsolute => f.write_str("path is not absolute"),
+            TypedPathError::NotRelative => f.write_str("path is not relative"),
+            TypedPathError::NotDirectory => f.write_str("path is not a directory"),
+            TypedPathError::NotFile => f.write_str("path is not a file"),
+            TypedPathError::Io(err) => write!(f, "couldn't check path: {err}"),
+        }
+    }
+}
+
+impl error::Error for TypedPathError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TypedPathError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+mod sealed {
+    use std::path::Path;
+
+    pub trait Sealed {
+        /// Casts `path` without checking it.
+        fn from_path_unchecked(path: &Path) -> &Self;
+    }
+}
+
+/// A path whose kind is known at compile time: one of [`AbsPath`],
+/// [`RelPath`], [`DirPath`] and [`FilePath`].
+pub trait TypedPath: AsRef<Path> + sealed::Sealed {}
+
+/// Which typed paths can be joined onto which, and what kind of path the
+/// result is.
+///
+/// | Left        | Right       | Result      |
+/// |-------------|-------------|-------------|
+/// | [`AbsPath`] | [`RelPath`] | [`AbsPath`] |
+/// | [`DirPath`] | [`RelPath`] | [`AbsPath`] |
+/// | [`RelPath`] | [`RelPath`] | [`RelPath`] |
+///
+/// Nothing can be joined onto a [`FilePath`], and nothing absolute can be
+/// joined onto anything, since `join_in_buff` would silently throw away
+/// everything before it.
+pub trait Join<Rhs: TypedPath + ?Sized>: TypedPath {
+    type Output: TypedPath + ?Sized;
+}
+
+macro_rules! typed_path {
+    ($(#[$attr:meta])* $name:ident => $target:ty) => {
+        $(#[$attr])*
+        #[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(transparent)]
+        pub struct $name(Path);
+
+        impl sealed::Sealed for $name {
+            fn from_path_unchecked(path: &Path) -> &Self {
+                // `repr(transparent)` guarantees the same layout as `Path`
+                unsafe { &*(path as *const Path as *const $name) }
+            }
+        }
+
+        impl TypedPath for $name {}
+
+        impl $name {
+            pub fn as_path(&self) -> &Path {
+                &self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = $target;
+
+            fn deref(&self) -> &$target {
+                sealed::Sealed::from_path_unchecked(&self.0)
+            }
+        }
+
+        impl AsRef<Path> for $name {
+            fn as_ref(&self) -> &Path {
+                &self.0
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl PartialEq<Path> for $name {
+            fn eq(&self, other: &Path) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+impl sealed::Sealed for Path {
+    fn from_path_unchecked(path: &Path) -> &Self {
+        path
+    }
+}
+
+typed_path! {
+    /// An absolute path.
+    AbsPath => Path
+}
+
+typed_path! {
+    /// A relative path, which is safe to join onto another path without
+    /// replacing it.
+    RelPath => Path
+}
+
+typed_path! {
+    /// An absolute path that named a directory when it was checked.
+    DirPath => AbsPath
+}
+
+typed_path! {
+    /// An absolute path that named a regular file when it was checked.
+    /// Nothing can be joined onto it.
+    FilePath => AbsPath
+}
+
+impl AbsPath {
+    /// Checks that `path` is absolute.
+    pub fn new<P: AsRef<Path> + ?Sized>(path: &P) -> Result<&AbsPath, TypedPathError> {
+        let path = path.as_ref();
+        if !path.is_absolute() {
+            return Err(TypedPathError::NotAbsolute);
+        }
+        Ok(sealed::Sealed::from_path_unchecked(path))
+    }
+}
+
+impl RelPath {
+    /// Checks that `path` is relative. Use this on values that are meant to
+    /// be joined onto something, such as paths from a config file.
+    pub fn new<P: AsRef<Path> + ?Sized>(path: &P) -> Result<&RelPath, TypedPathError> {
+        let path = path.as_ref();
+        if path.is_absolute() {
+            return Err(TypedPathError::NotRelative);
+        }
+        Ok(sealed::Sealed::from_path_unchecked(path))
+    }
+}
+
+impl DirPath {
+    /// Checks that `path` is absolute, and that it names a directory, after
+    /// following symlinks.
+    pub fn new<P: AsRef<Path> + ?Sized>(path: &P) -> Result<&DirPath, TypedPathError> {
+        let path = AbsPath::new(path)?.as_path();
+        let stat = StackPathBuf::<128>::from_path(path)
+            .stat()
+            .map_err(TypedPathError::Io)?;
+        if !stat.is_dir() {
+            return Err(TypedPathError::NotDirectory);
+        }
+        Ok(sealed::Sealed::from_path_unchecked(path))
+    }
+}
+
+impl FilePath {
+    /// Checks that `path` is absolute, and that it names a regular file,
+    /// after following symlinks.
+    pub fn new<P: AsRef<Path> + ?Sized>(path: &P) -> Result<&FilePath, TypedPathError> {
+        let path = AbsPath::new(path)?.as_path();
+        let stat = StackPathBuf::<128>::from_path(path)
+            .stat()
+            .map_err(TypedPathError::Io)?;
+        if !stat.is_file() {
+            return Err(TypedPathError::NotFile);
+        }
+        Ok(sealed::Sealed::from_path_unchecked(path))
+    }
+}
+
+impl Join<RelPath> for AbsPath {
+    type Output = AbsPath;
+}
+
+impl Join<RelPath> for DirPath {
+    type Output = AbsPath;
+}
+
+impl Join<RelPath> for RelPath {
+    type Output = RelPath;
+}
+
+/// The kind of path a `with_typed_paths!` declaration produces, worked out
+/// one join at a time.
+#[doc(hidden)]
+pub struct Typed<T: ?Sized>(PhantomData<fn() -> *const T>);
+
+#[doc(hidden)]
+pub fn typed_start<T: TypedPath + ?Sized>(_: &T) -> Typed<T> {
+    Typed(PhantomData)
+}
+
+#[doc(hidden)]
+pub fn typed_join<A, B>(_: Typed<A>, _: &B) -> Typed<A::Output>
+where
+    A: Join<B> + ?Sized,
+    B: TypedPath + ?Sized,
+{
+    Typed(PhantomData)
+}
+
+#[doc(hidden)]
+pub fn typed_finish<T: TypedPath + ?Sized>(_: Typed<T>, path: &Path) -> &T {
+    T::from_path_unchecked(path)
+}
+
+/// Like `with_paths!`, but every path is an [`AbsPath`], [`RelPath`],
+/// [`DirPath`] or [`FilePath`] reference, and the joins are checked at
+/// compile time against [`Join`]. Each declared variable is a reference to
+/// the resulting kind of path.
+///
+/// ```rust
+/// use path_no_alloc::{with_typed_paths, AbsPath, RelPath};
+///
+/// let root = AbsPath::new("/srv/app").unwrap();
+/// // Checked once, when the config is loaded
+/// let logs = RelPath::new("logs").unwrap();
+/// let name = RelPath::new("server.log").unwrap();
+///
+/// with_typed_paths! {
+///     log = root / logs / name,
+///     relative = logs / name
+/// };
+/// let log: &AbsPath = log;
+/// let relative: &RelPath = relative;
+/// assert_eq!(log.as_path(), std::path::Path::new("/srv/app/logs/server.log"));
+/// assert_eq!(relative.as_path(), std::path::Path::new("logs/server.log"));
+///
+/// assert!(RelPath::new("/etc/passwd").is_err());
+/// ```
+///
+/// Joining an absolute path onto another doesn't compile:
+///
+/// ```rust,compile_fail
+/// use path_no_alloc::{with_typed_paths, AbsPath};
+///
+/// let root = AbsPath::new("/srv/app").unwrap();
+/// let other = AbsPath::new("/etc").unwrap();
+/// with_typed_paths! {
+///     path = root / other
+/// };
+/// ```
+///
+/// And nor does joining anything onto a file:
+///
+/// ```rust,compile_fail
+/// use path_no_alloc::{with_typed_paths, FilePath, RelPath};
+///
+/// let file = FilePath::new("/etc/hostname").unwrap();
+/// let name = RelPath::new("x").unwrap();
+/// with_typed_paths! {
+///     path = file / name
+/// };
+/// ```
+#[macro_export]
+macro_rules! with_typed_paths {
+    // Works out the kind of the joined path
+    { @kind [$kind:expr] } => { $kind };
+    { @kind [$kind:expr] / $next:ident $( $rest:tt )* } => {
+        $crate::with_typed_paths!(@kind [$crate::__private::typed_join($kind, $next)] $( $rest )*)
+    };
+    { @start $first:ident $( $rest:tt )* } => {
+        $crate::with_typed_paths!(@kind [$crate::__private::typed_start($first)] $( $rest )*)
+    };
+
+    // Called by `__with_joined_paths!` for each declaration
+    { @join $arr:ident, $buff:ident, $( $path:ident ) / + } => {
+        $crate::__private::typed_finish(
+            $crate::with_typed_paths!(@start $( $path ) / +),
+            $crate::join_in_buff(&mut $arr, &mut $buff, [$($path.as_ref()),+]),
+        )
+    };
+
+    { $($tokens:tt)* } => {
+        $crate::__with_joined_paths!(with_typed_paths; $($tokens)*)
+    };
+}
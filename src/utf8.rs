@@ -0,0 +1,62 @@
+use std::mem::MaybeUninit;
+
+use crate::buffer::Writer;
+
+/// Joins N UTF-8 paths. If the paths fit inside the given buffer,
+/// uses the buffer. Otherwise, uses the given string.
+///
+/// Paths are joined exactly the way `PathBuf::push` joins them on the host:
+/// an absolute path replaces everything before it. Because every input is a
+/// `&str`, so is the result, and there's no need for `to_str().unwrap()`.
+pub fn join_in_buff_utf8<'a, const N: usize>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    str_buff: &'a mut Option<String>,
+    paths: [&str; N],
+) -> &'a str {
+    let mut out = Writer::new(raw_buff);
+    out.reserve(paths.iter().map(|p| p.len() + 1).sum());
+    for path in paths {
+        push_host(&mut out, path);
+    }
+    // Every byte written came from one of the paths or is a separator
+    unsafe { out.finish_str(str_buff) }
+}
+
+#[cfg(not(windows))]
+fn push_host(out: &mut Writer, path: &str) {
+    out.push_segment(path.as_bytes(), b'/')
+}
+
+#[cfg(windows)]
+fn push_host(out: &mut Writer, path: &str) {
+    crate::windows::push_windows(out, path.as_bytes())
+}
+
+/// Like `with_paths!`, but joins paths with [`join_in_buff_utf8`], so that
+/// every declared variable is a `&str`. Anything that implements
+/// `AsRef<str>` can be joined.
+///
+/// ```rust
+/// use path_no_alloc::with_utf8_paths;
+///
+/// let root = "logs";
+/// let file = String::from("server.log");
+///
+/// with_utf8_paths! {
+///     path = root / file
+/// };
+///
+/// let path: &str = path;
+/// # #[cfg(unix)]
+/// assert_eq!(path, "logs/server.log");
+/// ```
+#[macro_export]
+macro_rules! with_utf8_paths {
+    // Called by `__with_joined_paths!` for each declaration
+    { @join $arr:ident, $buff:ident, $( $path:ident ) / + } => {
+        $crate::join_in_buff_utf8(&mut $arr, &mut $buff, [$($path.as_ref()),+])
+    };
+    { $($tokens:tt)* } => {
+        $crate::__with_joined_paths!(with_utf8_paths; $($tokens)*)
+    };
+}
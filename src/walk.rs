@@ -0,0 +1,411 @@
+use std::{
+    ffi::{CStr, OsStr},
+    fmt, io,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+};
+
+use crate::{
+    read_dir::{DirStream, FileType},
+    sys, StackPathBuf,
+};
+
+/// Controls how a [`Walker`] walks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WalkOptions {
+    /// How deep to go. The root is at depth 0, and its entries are at
+    /// depth 1. Defaults to `usize::MAX`.
+    pub max_depth: usize,
+    /// Follow symlinks to directories, and report symlinks as the type of
+    /// their target. Defaults to `false`.
+    pub follow_links: bool,
+    /// Visit the entries of each directory in order of their names, instead
+    /// of the order the filesystem returns them in. This has to read each
+    /// directory in full before visiting it, which allocates. Defaults to
+    /// `false`.
+    pub sort: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            max_depth: usize::MAX,
+            follow_links: false,
+            sort: false,
+        }
+    }
+}
+
+/// Walks the directory tree under `root`. See [`Walker`].
+///
+/// ```rust
+/// use path_no_alloc::{walk, WalkOptions};
+///
+/// let options = WalkOptions { sort: true, ..WalkOptions::default() };
+/// let mut walker = walk("src", &options).unwrap();
+/// let mut rust_files = 0;
+/// while let Some(entry) = walker.next() {
+///     let entry = entry.unwrap();
+///     if entry.path().extension() == Some("rs".as_ref()) {
+///         rust_files += 1;
+///     }
+/// }
+/// assert!(rust_files > 10);
+/// ```
+pub fn walk(root: impl AsRef<Path>, options: &WalkOptions) -> io::Result<Walker> {
+    walk_with(root, options, |_| false)
+}
+
+/// Like [`walk`], but calls `prune` on every entry before yielding it. If it
+/// returns true, the entry is skipped, along with everything under it if
+/// it's a directory.
+pub fn walk_with<P>(
+    root: impl AsRef<Path>,
+    options: &WalkOptions,
+    prune: P,
+) -> io::Result<Walker<P>>
+where
+    P: FnMut(&WalkEntry<'_>) -> bool,
+{
+    let path = StackPathBuf::from_path(root);
+    let c_path = path.as_c_str()?;
+    let root = DirStream::open_at(libc::AT_FDCWD, c_path, true)?;
+    let root_id = file_id(root.fd())?;
+    Ok(Walker {
+        options: *options,
+        prune,
+        name_start: root_name_start(&path),
+        path,
+        stack: Vec::new(),
+        next: Next::Root(Frame::new(root, root_id)),
+    })
+}
+
+/// A lending iterator over a directory tree, in depth-first pre-order.
+///
+/// Every entry's path is built in a single buffer: descending into a
+/// directory appends to it, and moving on to the next entry only overwrites
+/// the last component. Besides that buffer, which only allocates once it
+/// outgrows its inline capacity, each open directory allocates an 8 KiB
+/// buffer to read its entries into. Subdirectories are opened with `openat`
+/// relative to their parent, so the full path is never resolved again.
+///
+/// Every directory between the root and the current entry stays open, so
+/// the walk holds one fd per level of depth. Going deeper than the process's
+/// fd limit allows fails with `EMFILE`, which is yielded as an error for
+/// that directory. Set [`WalkOptions::max_depth`] to bound it.
+///
+/// Since each entry borrows that buffer, this isn't an `Iterator`: use
+/// `while let Some(entry) = walker.next()`. The root itself is the first
+/// entry. Errors reading a directory are yielded, and the walk carries on
+/// with the next directory.
+pub struct Walker<P = fn(&WalkEntry<'_>) -> bool> {
+    options: WalkOptions,
+    prune: P,
+    path: StackPathBuf,
+    // Where the file name of the last entry starts in `path`
+    name_start: usize,
+    stack: Vec<Frame>,
+    next: Next,
+}
+
+/// What to do before reading the next entry.
+enum Next {
+    /// Yield the root, whose stream is already open.
+    Root(Frame),
+    /// Start walking a directory that's already open.
+    Push(Frame),
+    /// Open the last entry, a directory, and walk it.
+    Descend { via_link: bool },
+    /// Carry on with the current directory.
+    Read,
+}
+
+pub(crate) struct Frame {
+    pub(crate) stream: DirStream,
+    // Length of the directory's path, plus the separator
+    pub(crate) dir_len: usize,
+    // Device and inode, to detect loops when following links
+    pub(crate) id: (u64, u64),
+    sorted: Option<std::vec::IntoIter<SortedEntry>>,
+}
+
+struct SortedEntry {
+    name: Box<[u8]>,
+    file_type: FileType,
+    ino: u64,
+}
+
+impl Frame {
+    pub(crate) fn new(stream: DirStream, id: (u64, u64)) -> Frame {
+        Frame {
+            stream,
+            dir_len: 0,
+            id,
+            sorted: None,
+        }
+    }
+
+    pub(crate) fn read_sorted(&mut self) -> io::Result<()> {
+        let mut entries = Vec::new();
+        while let Some(entry) = self.stream.next_raw() {
+            let entry = entry?;
+            entries.push(SortedEntry {
+                name: entry.name.to_bytes().into(),
+                file_type: entry.file_type,
+                ino: entry.ino,
+            });
+        }
+        entries.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        self.sorted = Some(entries.into_iter());
+        Ok(())
+    }
+
+    /// Appends the next entry's name to `path`.
+    pub(crate) fn next_into(
+        &mut self,
+        path: &mut StackPathBuf,
+    ) -> Option<io::Result<(FileType, u64)>> {
+        path.truncate(self.dir_len);
+        if let Some(sorted) = &mut self.sorted {
+            let entry = sorted.next()?;
+            path.extend(&entry.name);
+            return Some(Ok((entry.file_type, entry.ino)));
+        }
+        match self.stream.next_raw()? {
+            Ok(entry) => {
+                path.extend(entry.name.to_bytes());
+                Some(Ok((entry.file_type, entry.ino)))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+pub(crate) fn file_id(fd: libc::c_int) -> io::Result<(u64, u64)> {
+    let stat = sys::fstat(fd)?;
+    Ok((stat.dev(), stat.ino()))
+}
+
+/// Returns where the root's file name starts in `path`, ignoring trailing
+/// separators. It's 0 if the path doesn't end in a normal component.
+pub(crate) fn root_name_start(path: &StackPathBuf) -> usize {
+    let bytes = path.as_bytes();
+    let trimmed = bytes.len() - bytes.iter().rev().take_while(|&&b| b == b'/').count();
+    let start = bytes[..trimmed]
+        .iter()
+        .rposition(|&b| b == b'/')
+        .map_or(0, |i| i + 1);
+    match &bytes[start..trimmed] {
+        b"" | b"." | b".." => 0,
+        _ => start,
+    }
+}
+
+/// Returns the file name that starts at `name_start` in `path`, as a
+/// null-terminated string.
+pub(crate) fn c_name(path: &StackPathBuf, name_start: usize) -> &CStr {
+    // The root's path is checked when the walk starts, and file names never
+    // contain null bytes
+    let c_path = path.as_c_str().unwrap();
+    CStr::from_bytes_with_nul(&c_path.to_bytes_with_nul()[name_start..]).unwrap()
+}
+
+/// Fills in the type of the entry `name` in `dir` with a `stat`, if the
+/// directory didn't report it, or if it's a symlink that should be followed.
+/// Broken symlinks are reported as symlinks.
+pub(crate) fn resolve_type(
+    dir: libc::c_int,
+    name: &CStr,
+    file_type: FileType,
+    follow_links: bool,
+) -> io::Result<FileType> {
+    let follow = file_type == FileType::Symlink && follow_links;
+    if file_type != FileType::Unknown && !follow {
+        return Ok(file_type);
+    }
+    match sys::stat_at(dir, name, follow) {
+        Ok(stat) => Ok(FileType::from_stat(&stat)),
+        Err(_) if follow => Ok(file_type),
+        Err(err) => Err(err),
+    }
+}
+
+impl<P> Walker<P>
+where
+    P: FnMut(&WalkEntry<'_>) -> bool,
+{
+    /// The path of the entry last returned by [`Walker::next`].
+    pub(crate) fn current_path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Reads the next entry.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<io::Result<WalkEntry<'_>>> {
+        loop {
+            match std::mem::replace(&mut self.next, Next::Read) {
+                Next::Root(frame) => {
+                    let ino = frame.id.1;
+                    if self.prune(0, FileType::Dir, ino) {
+                        return None;
+                    }
+                    if self.options.max_depth > 0 {
+                        self.next = Next::Push(frame);
+                    }
+                    return Some(Ok(self.entry(0, FileType::Dir, ino)));
+                }
+                Next::Push(frame) => {
+                    if let Err(err) = self.push_frame(frame) {
+                        return Some(Err(err));
+                    }
+                }
+                Next::Descend { via_link } => {
+                    if let Err(err) = self.descend(via_link) {
+                        return Some(Err(err));
+                    }
+                }
+                Next::Read => {}
+            }
+
+            let frame = self.stack.last_mut()?;
+            let (file_type, ino) = match frame.next_into(&mut self.path) {
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
+                Some(Err(err)) => {
+                    self.stack.pop();
+                    return Some(Err(err));
+                }
+                Some(Ok(entry)) => entry,
+            };
+            self.name_start = frame.dir_len;
+            let fd = frame.stream.fd();
+            let depth = self.stack.len();
+
+            let was_link = file_type == FileType::Symlink;
+            let name = c_name(&self.path, self.name_start);
+            let file_type = match resolve_type(fd, name, file_type, self.options.follow_links) {
+                Ok(file_type) => file_type,
+                Err(err) => return Some(Err(err)),
+            };
+
+            if self.prune(depth, file_type, ino) {
+                continue;
+            }
+            if file_type == FileType::Dir && depth < self.options.max_depth {
+                self.next = Next::Descend { via_link: was_link };
+            }
+            return Some(Ok(self.entry(depth, file_type, ino)));
+        }
+    }
+
+    fn prune(&mut self, depth: usize, file_type: FileType, ino: u64) -> bool {
+        let entry = WalkEntry {
+            path: &self.path,
+            name_start: self.name_start,
+            depth,
+            file_type,
+            ino,
+        };
+        (self.prune)(&entry)
+    }
+
+    fn entry(&self, depth: usize, file_type: FileType, ino: u64) -> WalkEntry<'_> {
+        WalkEntry {
+            path: &self.path,
+            name_start: self.name_start,
+            depth,
+            file_type,
+            ino,
+        }
+    }
+
+    /// Opens the last entry, which is a directory, and starts walking it.
+    fn descend(&mut self, via_link: bool) -> io::Result<()> {
+        let parent = self.stack.last().unwrap().stream.fd();
+        let name = c_name(&self.path, self.name_start);
+        let stream = DirStream::open_at(parent, name, via_link)?;
+        let id = file_id(stream.fd())?;
+        // Only a followed link can lead back to a directory that's already
+        // being walked
+        if via_link && self.stack.iter().any(|frame| frame.id == id) {
+            return Err(io::Error::from_raw_os_error(libc::ELOOP));
+        }
+        self.push_frame(Frame::new(stream, id))
+    }
+
+    fn push_frame(&mut self, mut frame: Frame) -> io::Result<()> {
+        if self.options.sort {
+            frame.read_sorted()?;
+        }
+        if self.path.len() > 0 && self.path.as_bytes().last() != Some(&b'/') {
+            self.path.extend(b"/");
+        }
+        frame.dir_len = self.path.len();
+        self.stack.push(frame);
+        Ok(())
+    }
+}
+
+/// An entry yielded by [`Walker`].
+#[derive(Clone, Copy)]
+pub struct WalkEntry<'a> {
+    pub(crate) path: &'a StackPathBuf,
+    pub(crate) name_start: usize,
+    pub(crate) depth: usize,
+    pub(crate) file_type: FileType,
+    pub(crate) ino: u64,
+}
+
+impl<'a> WalkEntry<'a> {
+    pub fn path(&self) -> &'a Path {
+        self.path.as_path()
+    }
+
+    /// The entry's path as a null-terminated string, ready to pass to the OS.
+    pub fn c_path(&self) -> &'a CStr {
+        self.path.as_c_str().unwrap()
+    }
+
+    /// The last component of the entry's path. For the root, this is the
+    /// whole path if it doesn't end in a normal component, such as `.`.
+    pub fn file_name(&self) -> &'a OsStr {
+        let mut name = &self.path.as_bytes()[self.name_start..];
+        // Only the root can end in a separator
+        while let [rest @ .., b'/'] = name {
+            if rest.is_empty() {
+                break;
+            }
+            name = rest;
+        }
+        OsStr::from_bytes(name)
+    }
+
+    /// How far below the root the entry is. The root is at depth 0.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The entry's type. Symlinks are reported as [`FileType::Symlink`],
+    /// unless the walk follows links.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// The entry's inode number.
+    pub fn ino(&self) -> u64 {
+        self.ino
+    }
+}
+
+impl fmt::Debug for WalkEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalkEntry")
+            .field("path", &self.path())
+            .field("depth", &self.depth)
+            .field("file_type", &self.file_type)
+            .finish()
+    }
+}
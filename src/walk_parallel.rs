@@ -0,0 +1,387 @@
+use std::{
+    collections::VecDeque,
+    io,
+    num::NonZeroUsize,
+    os::fd::{AsRawFd, OwnedFd},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    read_dir::{open_dir_at, DirStream, FileType},
+    walk::{c_name, file_id, resolve_type, root_name_start, Frame},
+    StackPathBuf, WalkEntry, WalkOptions,
+};
+
+/// How many queued directories can hold an open fd at once, across all
+/// workers. Past this, directories are opened when a worker picks them up,
+/// so a wide tree can't run out of fds.
+const MAX_QUEUED_FDS: usize = 64;
+
+/// How a queued directory gets opened.
+enum JobDir {
+    // Opened with `openat` when it was queued
+    Open(OwnedFd),
+    // Too many fds were queued already, so it's opened with `openat` from
+    // its parent once it's picked up. The parent's fd is a duplicate shared
+    // by all of its children that are queued this way.
+    At {
+        parent: Arc<OwnedFd>,
+        name_start: usize,
+    },
+}
+
+/// A directory waiting to be walked.
+struct Job {
+    dir: JobDir,
+    path: StackPathBuf,
+    depth: usize,
+    // Whether it was reached through a symlink, so that opening it follows
+    // the link
+    via_link: bool,
+    // The directories above it, when following links
+    ancestors: Option<Arc<Ancestor>>,
+}
+
+/// The directories above a job, when following links, to detect loops.
+struct Ancestor {
+    id: (u64, u64),
+    parent: Option<Arc<Ancestor>>,
+}
+
+/// Counts the jobs that are queued or being worked on, and parks idle
+/// workers until there's another job or none are left.
+struct Pending {
+    count: AtomicUsize,
+    // How many workers are waiting on `wake`
+    idle: Mutex<usize>,
+    wake: Condvar,
+}
+
+impl Pending {
+    fn is_done(&self) -> bool {
+        self.count.load(Ordering::Acquire) == 0
+    }
+
+    /// Wakes an idle worker, if there is one, after a job has been queued.
+    fn notify(&self) {
+        if *self.idle.lock().unwrap() > 0 {
+            self.wake.notify_one();
+        }
+    }
+}
+
+/// Marks a job as done when dropped, even if `visit` panics, so that the
+/// other workers still stop and the panic reaches the caller.
+struct Done<'a>(&'a Pending);
+
+impl Drop for Done<'_> {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Taking the lock means every idle worker is either waiting, and
+            // gets woken, or yet to check `is_done`
+            let _idle = self.0.idle.lock().unwrap();
+            self.0.wake.notify_all();
+        }
+    }
+}
+
+struct Shared<'a, F> {
+    options: &'a WalkOptions,
+    visit: &'a F,
+    queues: Vec<Mutex<VecDeque<Job>>>,
+    pending: Pending,
+    // Queued jobs that hold an fd
+    queued_fds: AtomicUsize,
+}
+
+/// Walks the directory tree under `root` on `threads` threads, calling
+/// `visit` on every entry. If `threads` is 0, uses one thread per CPU.
+///
+/// `visit` returns whether to descend into the entry, if it's a directory,
+/// which is how subtrees are pruned; the return value is ignored for
+/// errors. It's called from several threads at once, in no particular
+/// order, except that a directory is always visited before its entries.
+/// With [`WalkOptions::sort`], the entries of each directory are visited in
+/// order by the one thread that reads it.
+///
+/// Each worker joins entry paths in its own path buffer. When it finds a
+/// subdirectory, it opens it with `openat` and queues it, along with a copy
+/// of its path, on its own deque, and idle workers steal from the other end
+/// of other workers' deques. Only 64 queued directories hold an fd at a
+/// time. The rest hold a duplicate of their parent's fd, shared between
+/// siblings, and are opened with `openat` once they're picked up, so wide
+/// trees don't run out of fds. Directories are always opened relative to
+/// their parent, never by their full path.
+///
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use path_no_alloc::{walk_parallel, WalkOptions};
+///
+/// let rust_files = AtomicUsize::new(0);
+/// walk_parallel("src", &WalkOptions::default(), 4, |entry| {
+///     let entry = entry.unwrap();
+///     if entry.path().extension() == Some("rs".as_ref()) {
+///         rust_files.fetch_add(1, Ordering::Relaxed);
+///     }
+///     true
+/// })
+/// .unwrap();
+/// assert!(rust_files.into_inner() > 10);
+/// ```
+pub fn walk_parallel<F>(
+    root: impl AsRef<Path>,
+    options: &WalkOptions,
+    threads: usize,
+    visit: F,
+) -> io::Result<()>
+where
+    F: Fn(io::Result<WalkEntry<'_>>) -> bool + Sync,
+{
+    let path = StackPathBuf::from_path(root);
+    let fd = open_dir_at(libc::AT_FDCWD, path.as_c_str()?, true)?;
+    let id = file_id(fd.as_raw_fd())?;
+
+    let root = WalkEntry {
+        path: &path,
+        name_start: root_name_start(&path),
+        depth: 0,
+        file_type: FileType::Dir,
+        ino: id.1,
+    };
+    if !visit(Ok(root)) || options.max_depth == 0 {
+        return Ok(());
+    }
+
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        n => n,
+    };
+    let shared = Shared {
+        options,
+        visit: &visit,
+        queues: (0..threads).map(|_| Mutex::default()).collect(),
+        pending: Pending {
+            count: AtomicUsize::new(1),
+            idle: Mutex::new(0),
+            wake: Condvar::new(),
+        },
+        queued_fds: AtomicUsize::new(1),
+    };
+    shared.queues[0].lock().unwrap().push_back(Job {
+        dir: JobDir::Open(fd),
+        path,
+        depth: 0,
+        via_link: false,
+        ancestors: None,
+    });
+
+    thread::scope(|scope| {
+        for index in 0..threads {
+            let shared = &shared;
+            scope.spawn(move || shared.work(index));
+        }
+    });
+    Ok(())
+}
+
+impl<F> Shared<'_, F>
+where
+    F: Fn(io::Result<WalkEntry<'_>>) -> bool + Sync,
+{
+    fn work(&self, index: usize) {
+        let mut path = StackPathBuf::new();
+        while let Some(job) = self.take(index).or_else(|| self.wait(index)) {
+            let _done = Done(&self.pending);
+            self.run(index, job, &mut path);
+        }
+    }
+
+    /// Parks until there's a job to take, or returns `None` once every job
+    /// is done.
+    fn wait(&self, index: usize) -> Option<Job> {
+        let mut idle = self.pending.idle.lock().unwrap();
+        loop {
+            // Checked with the lock held, so that a job queued or finished in
+            // the meantime can't be missed
+            if self.pending.is_done() {
+                return None;
+            }
+            if let Some(job) = self.take(index) {
+                return Some(job);
+            }
+            *idle += 1;
+            idle = self.pending.wake.wait(idle).unwrap();
+            *idle -= 1;
+        }
+    }
+
+    /// Takes the newest job from this worker's deque, or steals the oldest
+    /// one from another worker's.
+    fn take(&self, index: usize) -> Option<Job> {
+        if let Some(job) = self.queues[index].lock().unwrap().pop_back() {
+            return Some(job);
+        }
+        let count = self.queues.len();
+        (1..count).find_map(|i| {
+            let victim = &self.queues[(index + i) % count];
+            victim.lock().unwrap().pop_front()
+        })
+    }
+
+    fn run(&self, index: usize, job: Job, path: &mut StackPathBuf) {
+        path.clear();
+        path.extend(job.path.as_bytes());
+        let depth = job.depth + 1;
+        let (fd, ancestors) = match self.open(job, path) {
+            Ok(opened) => opened,
+            Err(err) => {
+                (self.visit)(Err(err));
+                return;
+            }
+        };
+        if path.len() > 0 && path.as_bytes().last() != Some(&b'/') {
+            path.extend(b"/");
+        }
+
+        let id = ancestors.as_ref().map_or((0, 0), |a| a.id);
+        let mut frame = Frame::new(DirStream::new(fd), id);
+        // A duplicate of `frame`'s fd, once a subdirectory is queued without
+        // its own
+        let mut shared_fd = None;
+        frame.dir_len = path.len();
+        if self.options.sort {
+            if let Err(err) = frame.read_sorted() {
+                (self.visit)(Err(err));
+                return;
+            }
+        }
+
+        while let Some(entry) = frame.next_into(path) {
+            let (file_type, ino) = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    (self.visit)(Err(err));
+                    return;
+                }
+            };
+            let name = c_name(path, frame.dir_len);
+            let was_link = file_type == FileType::Symlink;
+            let file_type = match resolve_type(
+                frame.stream.fd(),
+                name,
+                file_type,
+                self.options.follow_links,
+            ) {
+                Ok(file_type) => file_type,
+                Err(err) => {
+                    (self.visit)(Err(err));
+                    continue;
+                }
+            };
+
+            let entry = WalkEntry {
+                path: &*path,
+                name_start: frame.dir_len,
+                depth,
+                file_type,
+                ino,
+            };
+            let descend = (self.visit)(Ok(entry));
+            if descend && file_type == FileType::Dir && depth < self.options.max_depth {
+                let job = self
+                    .job_dir(&frame, &mut shared_fd, path, was_link)
+                    .map(|dir| Job {
+                        dir,
+                        path: path.clone(),
+                        depth,
+                        via_link: was_link,
+                        ancestors: ancestors.clone(),
+                    });
+                match job {
+                    Ok(job) => self.queue(index, job),
+                    Err(err) => {
+                        (self.visit)(Err(err));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Opens the job's directory, unless it was opened when it was queued,
+    /// and checks it for a loop when following links. `path` is the job's
+    /// path.
+    fn open(&self, job: Job, path: &StackPathBuf) -> io::Result<(OwnedFd, Option<Arc<Ancestor>>)> {
+        let fd = match job.dir {
+            JobDir::Open(fd) => {
+                self.queued_fds.fetch_sub(1, Ordering::AcqRel);
+                fd
+            }
+            JobDir::At { parent, name_start } => {
+                open_dir_at(parent.as_raw_fd(), c_name(path, name_start), job.via_link)?
+            }
+        };
+        if !self.options.follow_links {
+            return Ok((fd, None));
+        }
+
+        let id = file_id(fd.as_raw_fd())?;
+        let mut ancestor = job.ancestors.as_ref();
+        while let Some(a) = ancestor {
+            if a.id == id {
+                return Err(io::Error::from_raw_os_error(libc::ELOOP));
+            }
+            ancestor = a.parent.as_ref();
+        }
+        let ancestors = Arc::new(Ancestor {
+            id,
+            parent: job.ancestors,
+        });
+        Ok((fd, Some(ancestors)))
+    }
+
+    /// Opens the entry at the end of `path` to be queued, if not too many
+    /// queued directories are open already. Otherwise it's left to be
+    /// opened from `shared_fd`, which is duplicated from `frame` the first
+    /// time it's needed.
+    fn job_dir(
+        &self,
+        frame: &Frame,
+        shared_fd: &mut Option<Arc<OwnedFd>>,
+        path: &StackPathBuf,
+        via_link: bool,
+    ) -> io::Result<JobDir> {
+        if self.queued_fds.fetch_add(1, Ordering::AcqRel) < MAX_QUEUED_FDS {
+            let name = c_name(path, frame.dir_len);
+            return match open_dir_at(frame.stream.fd(), name, via_link) {
+                Ok(fd) => Ok(JobDir::Open(fd)),
+                Err(err) => {
+                    self.queued_fds.fetch_sub(1, Ordering::AcqRel);
+                    Err(err)
+                }
+            };
+        }
+        self.queued_fds.fetch_sub(1, Ordering::AcqRel);
+        let parent = match shared_fd {
+            Some(fd) => fd.clone(),
+            None => shared_fd
+                .insert(Arc::new(frame.stream.try_clone_fd()?))
+                .clone(),
+        };
+        Ok(JobDir::At {
+            parent,
+            name_start: frame.dir_len,
+        })
+    }
+
+    /// Queues a job on this worker's deque.
+    fn queue(&self, index: usize, job: Job) {
+        self.pending.count.fetch_add(1, Ordering::AcqRel);
+        self.queues[index].lock().unwrap().push_back(job);
+        self.pending.notify();
+    }
+}
@@ -0,0 +1,111 @@
+use std::{borrow::Cow, env, ffi::OsStr, os::unix::ffi::OsStrExt, path::Path};
+
+use crate::StackPathBuf;
+
+/// Looks up `name` in the directories listed in `PATH`, and returns the first
+/// match that's an executable file.
+///
+/// Like a shell, an empty entry in `PATH` means the current directory, and a
+/// `name` that contains a `/` isn't looked up at all: it's only checked
+/// directly. Reading `PATH` is the only allocation; each candidate is built
+/// in one reused buffer. A file counts as executable if it's a regular file
+/// with any execute bit set.
+///
+/// ```rust
+/// use path_no_alloc::which;
+///
+/// let sh = which("sh").unwrap();
+/// assert!(sh.ends_with("sh"));
+/// assert!(which("path-no-alloc-missing").is_none());
+/// ```
+pub fn which(name: impl AsRef<Path>) -> Option<StackPathBuf> {
+    which_in(name, env::var_os("PATH")?)
+}
+
+/// Like [`which`], but searches the given `:`-separated list of directories
+/// instead of `PATH`.
+pub fn which_in(name: impl AsRef<Path>, path_list: impl AsRef<OsStr>) -> Option<StackPathBuf> {
+    which_all_in(name, path_list.as_ref()).next()
+}
+
+/// Like [`which`], but returns every match, in `PATH` order.
+pub fn which_all(name: impl AsRef<Path>) -> WhichAll<'static> {
+    let list = env::var_os("PATH").unwrap_or_default();
+    WhichAll::new(name.as_ref(), Cow::Owned(list))
+}
+
+/// Like [`which_all`], but searches the given `:`-separated list of
+/// directories instead of `PATH`.
+pub fn which_all_in<P: AsRef<OsStr> + ?Sized>(
+    name: impl AsRef<Path>,
+    path_list: &P,
+) -> WhichAll<'_> {
+    WhichAll::new(name.as_ref(), Cow::Borrowed(path_list.as_ref()))
+}
+
+/// An iterator over the executables matching a name. See [`which_all`].
+#[derive(Clone, Debug)]
+pub struct WhichAll<'a> {
+    name: StackPathBuf,
+    path_list: Cow<'a, OsStr>,
+    // Where the next entry starts, or `None` once the list is used up
+    pos: Option<usize>,
+    // The name contains a slash, so it's checked as-is instead
+    direct: bool,
+    path: StackPathBuf,
+}
+
+impl<'a> WhichAll<'a> {
+    fn new(name: &Path, path_list: Cow<'a, OsStr>) -> Self {
+        let bytes = name.as_os_str().as_bytes();
+        let direct = bytes.contains(&b'/');
+        let pos = (!bytes.is_empty() && !direct).then_some(0);
+        WhichAll {
+            name: StackPathBuf::from_path(name),
+            path_list,
+            pos,
+            direct,
+            path: StackPathBuf::new(),
+        }
+    }
+}
+
+impl Iterator for WhichAll<'_> {
+    type Item = StackPathBuf;
+
+    fn next(&mut self) -> Option<StackPathBuf> {
+        if std::mem::take(&mut self.direct) {
+            self.path.push(&self.name);
+            return self
+                .path
+                .stat()
+                .is_ok_and(|s| s.is_executable())
+                .then(|| self.path.clone());
+        }
+
+        while let Some(pos) = self.pos {
+            let rest = &self.path_list.as_bytes()[pos..];
+            let dir = match rest.iter().position(|&b| b == b':') {
+                Some(end) => {
+                    self.pos = Some(pos + end + 1);
+                    &rest[..end]
+                }
+                None => {
+                    self.pos = None;
+                    rest
+                }
+            };
+
+            self.path.clear();
+            self.path.push(match dir {
+                b"" => OsStr::new("."),
+                dir => OsStr::from_bytes(dir),
+            });
+            self.path.push(&self.name);
+            if self.path.stat().is_ok_and(|s| s.is_executable()) {
+                return Some(self.path.clone());
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,411 @@
+use std::{fmt, mem::MaybeUninit};
+
+use crate::buffer::Writer;
+
+/// The prefix of a Windows path, parsed the same way as
+/// `std::path::Prefix`, but available on every host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WindowsPrefix<'a> {
+    /// `\\?\prefix`
+    Verbatim(&'a [u8]),
+    /// `\\?\UNC\server\share`
+    VerbatimUNC(&'a [u8], &'a [u8]),
+    /// `\\?\C:`, where the byte is the drive letter
+    VerbatimDisk(u8),
+    /// `\\.\device`
+    DeviceNS(&'a [u8]),
+    /// `\\server\share`
+    UNC(&'a [u8], &'a [u8]),
+    /// `C:`, where the byte is the drive letter
+    Disk(u8),
+}
+
+impl WindowsPrefix<'_> {
+    /// Returns true for the `\\?\` prefixes, which turn off all path
+    /// normalization, including treating `/` as a separator.
+    pub fn is_verbatim(&self) -> bool {
+        matches!(
+            self,
+            WindowsPrefix::Verbatim(_)
+                | WindowsPrefix::VerbatimUNC(..)
+                | WindowsPrefix::VerbatimDisk(_)
+        )
+    }
+
+    fn is_drive(&self) -> bool {
+        matches!(self, WindowsPrefix::Disk(_))
+    }
+}
+
+/// A component of a [`WindowsPath`], analogous to `std::path::Component`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WindowsComponent<'a> {
+    Prefix(WindowsPrefix<'a>),
+    RootDir,
+    CurDir,
+    ParentDir,
+    Normal(&'a [u8]),
+}
+
+/// A Windows path that can be parsed and joined on any host.
+///
+/// On Linux, `std::path` treats `C:\foo` as a single relative component.
+/// `WindowsPath` works on the raw bytes instead, and understands drive
+/// letters, UNC shares, and the `\\?\` and `\\.\` prefixes.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct WindowsPath([u8]);
+
+impl WindowsPath {
+    /// Wraps a string or byte slice as a `WindowsPath`.
+    pub fn new<S: AsRef<[u8]> + ?Sized>(s: &S) -> &WindowsPath {
+        let bytes: &[u8] = s.as_ref();
+        // WindowsPath is repr(transparent) over [u8]
+        unsafe { &*(bytes as *const [u8] as *const WindowsPath) }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the path as a `&str`, if it's valid UTF-8.
+    pub fn to_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    pub fn prefix(&self) -> Option<WindowsPrefix<'_>> {
+        parse_prefix(&self.0).map(|(prefix, _)| prefix)
+    }
+
+    /// Returns true if the path has a root directory, either because it has a
+    /// separator right after the prefix, or because the prefix implies one
+    /// (everything other than `C:`).
+    pub fn has_root(&self) -> bool {
+        let parsed = Parsed::new(&self.0);
+        parsed.has_physical_root || parsed.prefix.is_some_and(|p| !p.is_drive())
+    }
+
+    /// Returns true if the path is absolute, i.e. if it has both a prefix and
+    /// a root. `\foo` and `C:foo` are both relative.
+    pub fn is_absolute(&self) -> bool {
+        self.0.starts_with(br"\\?\") || (self.prefix().is_some() && self.has_root())
+    }
+
+    /// Iterates over the components of the path. Empty components and `.`
+    /// are skipped, except that `.` is kept in verbatim paths.
+    pub fn components(&self) -> impl DoubleEndedIterator<Item = WindowsComponent<'_>> {
+        let parsed = Parsed::new(&self.0);
+        let verbatim = parsed.verbatim();
+        let prefix = parsed.prefix.map(WindowsComponent::Prefix);
+        let root = (parsed.has_physical_root || parsed.prefix.is_some_and(|p| !p.is_drive()))
+            .then_some(WindowsComponent::RootDir);
+        let rest = parsed.rest.split(move |b| is_sep(*b, verbatim));
+        prefix
+            .into_iter()
+            .chain(root)
+            .chain(rest.filter_map(move |c| match c {
+                b"" => None,
+                b"." if verbatim => Some(WindowsComponent::CurDir),
+                b"." => None,
+                b".." => Some(WindowsComponent::ParentDir),
+                c => Some(WindowsComponent::Normal(c)),
+            }))
+    }
+
+    /// Returns the last component of the path, if it's a normal component.
+    pub fn file_name(&self) -> Option<&[u8]> {
+        match self.components().next_back() {
+            Some(WindowsComponent::Normal(name)) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl AsRef<[u8]> for WindowsPath {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for WindowsPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&String::from_utf8_lossy(&self.0), f)
+    }
+}
+
+impl fmt::Display for WindowsPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&String::from_utf8_lossy(&self.0), f)
+    }
+}
+
+fn is_sep(b: u8, verbatim: bool) -> bool {
+    b == b'\\' || (!verbatim && b == b'/')
+}
+
+/// A path split into its prefix, whether there's a separator after the
+/// prefix, and everything after that.
+struct Parsed<'a> {
+    prefix: Option<WindowsPrefix<'a>>,
+    prefix_len: usize,
+    has_physical_root: bool,
+    rest: &'a [u8],
+}
+
+impl<'a> Parsed<'a> {
+    fn new(path: &'a [u8]) -> Self {
+        let (prefix, prefix_len) = match parse_prefix(path) {
+            Some((prefix, len)) => (Some(prefix), len),
+            None => (None, 0),
+        };
+        let verbatim = prefix.is_some_and(|p| p.is_verbatim());
+        let rest = &path[prefix_len..];
+        let has_physical_root = rest.first().is_some_and(|b| is_sep(*b, verbatim));
+        Parsed {
+            prefix,
+            prefix_len,
+            has_physical_root,
+            rest,
+        }
+    }
+
+    fn verbatim(&self) -> bool {
+        self.prefix.is_some_and(|p| p.is_verbatim())
+    }
+}
+
+/// Splits off the next component, returning it and everything after the
+/// separator that ended it.
+fn next_component(path: &[u8], verbatim: bool) -> (&[u8], &[u8]) {
+    match path.iter().position(|b| is_sep(*b, verbatim)) {
+        Some(idx) => (&path[..idx], &path[idx + 1..]),
+        None => (path, &[]),
+    }
+}
+
+fn parse_drive(path: &[u8]) -> Option<u8> {
+    match path {
+        [drive, b':', ..] if drive.is_ascii_alphabetic() => Some(drive.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// Strips `prefix` off the start of `path`, where a `/` in `path` can stand
+/// in for each `\` in `prefix`.
+fn strip_prefix_any_sep<'a>(path: &'a [u8], prefix: &[u8]) -> Option<&'a [u8]> {
+    let head = path.get(..prefix.len())?;
+    let matches = head
+        .iter()
+        .zip(prefix)
+        .all(|(&b, &p)| b == p || (p == b'\\' && b == b'/'));
+    matches.then(|| &path[prefix.len()..])
+}
+
+/// Parses the prefix of a path, returning it along with its length in bytes.
+/// Like on Windows, `/` works as a separator in every prefix but a verbatim
+/// one, so `//server/share` is a UNC prefix but `//?/C:` isn't verbatim.
+fn parse_prefix(path: &[u8]) -> Option<(WindowsPrefix<'_>, usize)> {
+    // Length of everything consumed, given what's left
+    let consumed = |rest: &[u8]| path.len() - rest.len();
+
+    if let Some(rest) = strip_prefix_any_sep(path, br"\\") {
+        if let Some(rest) = path.strip_prefix(br"\\?\") {
+            if let Some(rest) = rest.strip_prefix(br"UNC\") {
+                let (server, rest) = next_component(rest, true);
+                let (share, _) = next_component(rest, true);
+                let len = consumed(rest) + share.len();
+                Some((WindowsPrefix::VerbatimUNC(server, share), len))
+            } else {
+                let (prefix, _) = next_component(rest, true);
+                let len = consumed(rest) + prefix.len();
+                match prefix {
+                    [drive, b':'] if drive.is_ascii_alphabetic() => {
+                        Some((WindowsPrefix::VerbatimDisk(drive.to_ascii_uppercase()), len))
+                    }
+                    _ => Some((WindowsPrefix::Verbatim(prefix), len)),
+                }
+            }
+        } else if let Some(rest) = strip_prefix_any_sep(rest, br".\") {
+            let (device, _) = next_component(rest, false);
+            Some((
+                WindowsPrefix::DeviceNS(device),
+                consumed(rest) + device.len(),
+            ))
+        } else {
+            let (server, rest) = next_component(rest, false);
+            let (share, _) = next_component(rest, false);
+            if server.is_empty() || share.is_empty() {
+                return None;
+            }
+            Some((
+                WindowsPrefix::UNC(server, share),
+                consumed(rest) + share.len(),
+            ))
+        }
+    } else {
+        parse_drive(path).map(|drive| (WindowsPrefix::Disk(drive), 2))
+    }
+}
+
+/// Pushes `path` onto `out` with the rules `PathBuf::push` uses on Windows.
+pub(crate) fn push_windows(out: &mut Writer, path: &[u8]) {
+    let current = Parsed::new(out.as_bytes());
+    let current_prefix_len = current.prefix_len;
+    let current_verbatim = current.verbatim();
+    let current_root_len = current_prefix_len + current.has_physical_root as usize;
+    // `C:` followed by `foo` is `C:foo`, not `C:\foo`
+    let need_sep = out.last().is_some_and(|b| !is_sep(b, false))
+        && !(current.prefix.is_some_and(|p| p.is_drive()) && current.rest.is_empty());
+
+    let pushed = WindowsPath::new(path);
+    if pushed.is_absolute() || pushed.prefix().is_some() {
+        out.truncate(0);
+        out.extend(path);
+    } else if current_verbatim && !path.is_empty() {
+        // Verbatim paths aren't normalized by the OS, so `.` and `..` have to
+        // be resolved here
+        for component in pushed.components() {
+            match component {
+                WindowsComponent::RootDir => out.truncate(current_root_len),
+                WindowsComponent::CurDir | WindowsComponent::Prefix(_) => {}
+                WindowsComponent::ParentDir => {
+                    let bytes = out.as_bytes();
+                    if bytes.len() > current_root_len {
+                        let parent = bytes[current_root_len..]
+                            .iter()
+                            .rposition(|b| *b == b'\\')
+                            .map_or(current_root_len, |idx| current_root_len + idx);
+                        out.truncate(parent);
+                    }
+                }
+                WindowsComponent::Normal(name) => {
+                    if out.last().is_some_and(|b| b != b'\\') {
+                        out.push(b'\\');
+                    }
+                    out.extend(name);
+                }
+            }
+        }
+    } else if pushed.has_root() {
+        // `\windows` keeps the prefix of whatever it's pushed onto
+        out.truncate(current_prefix_len);
+        out.extend(path);
+    } else {
+        if need_sep {
+            out.push(b'\\');
+        }
+        out.extend(path);
+    }
+}
+
+/// Joins N Windows paths. If the paths fit inside the given buffer,
+/// uses the buffer. Otherwise, uses the given byte buffer.
+///
+/// Paths are joined the way `PathBuf::push` joins them on Windows: absolute
+/// paths and paths with a prefix replace everything before them, and a
+/// rooted path like `\windows` keeps only the prefix of what came before.
+pub fn join_windows_in_buff<'a, const N: usize>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    bytes_buff: &'a mut Option<Vec<u8>>,
+    paths: [&[u8]; N],
+) -> &'a WindowsPath {
+    let mut out = Writer::new(raw_buff);
+    out.reserve(paths.iter().map(|p| p.len() + 1).sum());
+    for path in paths {
+        push_windows(&mut out, path);
+    }
+    WindowsPath::new(out.finish_bytes(bytes_buff))
+}
+
+/// Lexically normalizes a Windows path. If the result fits inside the given
+/// buffer, uses the buffer. Otherwise, uses the given byte buffer.
+///
+/// Separators become `\`, empty and `.` components are removed, and `..`
+/// removes the component before it. A `..` directly under the root is
+/// dropped, and leading `..` components of a relative path are kept.
+/// Verbatim paths are returned unchanged, since Windows doesn't normalize
+/// them either.
+pub fn normalize_windows_in_buff<'a>(
+    raw_buff: &'a mut [MaybeUninit<u8>],
+    bytes_buff: &'a mut Option<Vec<u8>>,
+    path: &[u8],
+) -> &'a WindowsPath {
+    let mut out = Writer::new(raw_buff);
+    out.reserve(path.len() + 1);
+
+    let parsed = Parsed::new(path);
+    if parsed.verbatim() {
+        out.extend(path);
+        return WindowsPath::new(out.finish_bytes(bytes_buff));
+    }
+
+    for (i, b) in path[..parsed.prefix_len].iter().enumerate() {
+        // The `\\` and `\\.\` parts of a prefix have to stay as they are
+        let b = if i >= 2 && *b == b'/' { b'\\' } else { *b };
+        out.push(b);
+    }
+    let rooted = WindowsPath::new(path).has_root();
+    if rooted {
+        out.push(b'\\');
+    }
+    let base_len = out.len();
+
+    for component in parsed.rest.split(|b| is_sep(*b, false)) {
+        match component {
+            b"" | b"." => {}
+            b".." => {
+                let bytes = &out.as_bytes()[base_len..];
+                let empty = bytes.is_empty();
+                let parent = bytes.iter().rposition(|b| *b == b'\\');
+                let last = &bytes[parent.map_or(0, |idx| idx + 1)..];
+                if empty && rooted {
+                    // Can't go above the root
+                } else if empty || last == b".." {
+                    if !empty {
+                        out.push(b'\\');
+                    }
+                    out.extend(b"..");
+                } else {
+                    out.truncate(base_len + parent.unwrap_or(0));
+                }
+            }
+            component => {
+                if out.len() > base_len {
+                    out.push(b'\\');
+                }
+                out.extend(component);
+            }
+        }
+    }
+    WindowsPath::new(out.finish_bytes(bytes_buff))
+}
+
+/// Like `with_paths!`, but joins paths with [`join_windows_in_buff`], so that
+/// every declared variable is a [`&WindowsPath`](WindowsPath) built with
+/// Windows rules on every platform. Anything that implements `AsRef<[u8]>`
+/// (including `&str` and `String`) can be joined.
+///
+/// ```rust
+/// use path_no_alloc::with_windows_paths;
+///
+/// let drive = r"C:\Users";
+/// let user = "alecto";
+/// let rooted = r"\Windows";
+///
+/// with_windows_paths! {
+///     home = drive / user,
+///     windows = drive / user / rooted
+/// };
+/// assert_eq!(home.to_str(), Some(r"C:\Users\alecto"));
+/// assert_eq!(windows.to_str(), Some(r"C:\Windows"));
+/// ```
+#[macro_export]
+macro_rules! with_windows_paths {
+    // Called by `__with_joined_paths!` for each declaration
+    { @join $arr:ident, $buff:ident, $( $path:ident ) / + } => {
+        $crate::join_windows_in_buff(&mut $arr, &mut $buff, [$($path.as_ref()),+])
+    };
+    { $($tokens:tt)* } => {
+        $crate::__with_joined_paths!(with_windows_paths; $($tokens)*)
+    };
+}
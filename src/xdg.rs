@@ -0,0 +1,176 @@
+//! XDG base directory lookups, following the
+//! [XDG Base Directory Specification](https://specifications.freedesktop.org/basedir-spec/latest/).
+//!
+//! [`place`] and [`find`] read the environment. The `_with` versions take a
+//! function to look variables up with instead, as here:
+//!
+//! ```rust
+//! use std::{
+//!     ffi::{OsStr, OsString},
+//!     path::Path,
+//! };
+//! use path_no_alloc::xdg::{self, BaseDir};
+//!
+//! let env = |name: &OsStr| match name.to_str()? {
+//!     "HOME" => Some(OsString::from("/home/alice")),
+//!     "XDG_CONFIG_DIRS" => Some(OsString::from(env!("CARGO_MANIFEST_DIR"))),
+//!     _ => None,
+//! };
+//!
+//! // Where to write the config file
+//! let place = xdg::place_with(BaseDir::Config, "my-app/config.toml", env).unwrap();
+//! assert_eq!(&*place, Path::new("/home/alice/.config/my-app/config.toml"));
+//!
+//! // Where to read a file from, if it exists anywhere
+//! let found = xdg::find_with(BaseDir::Config, "Cargo.toml", env).unwrap();
+//! assert_eq!(&*found, Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"));
+//! assert!(xdg::find_with(BaseDir::Config, "my-app/config.toml", env).is_none());
+//! ```
+
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    os::unix::ffi::OsStrExt,
+    path::Path,
+};
+
+use crate::StackPathBuf;
+
+/// One of the kinds of base directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BaseDir {
+    /// `$XDG_CONFIG_HOME`, defaulting to `~/.config`, then
+    /// `$XDG_CONFIG_DIRS`, defaulting to `/etc/xdg`.
+    Config,
+    /// `$XDG_DATA_HOME`, defaulting to `~/.local/share`, then
+    /// `$XDG_DATA_DIRS`, defaulting to `/usr/local/share:/usr/share`.
+    Data,
+    /// `$XDG_CACHE_HOME`, defaulting to `~/.cache`.
+    Cache,
+    /// `$XDG_STATE_HOME`, defaulting to `~/.local/state`.
+    State,
+    /// `$XDG_RUNTIME_DIR`, which has no default.
+    Runtime,
+}
+
+impl BaseDir {
+    fn home_var(self) -> &'static str {
+        match self {
+            BaseDir::Config => "XDG_CONFIG_HOME",
+            BaseDir::Data => "XDG_DATA_HOME",
+            BaseDir::Cache => "XDG_CACHE_HOME",
+            BaseDir::State => "XDG_STATE_HOME",
+            BaseDir::Runtime => "XDG_RUNTIME_DIR",
+        }
+    }
+
+    /// Where the directory is relative to `$HOME` if its variable isn't set.
+    fn home_default(self) -> Option<&'static str> {
+        match self {
+            BaseDir::Config => Some(".config"),
+            BaseDir::Data => Some(".local/share"),
+            BaseDir::Cache => Some(".cache"),
+            BaseDir::State => Some(".local/state"),
+            BaseDir::Runtime => None,
+        }
+    }
+
+    fn dirs_var(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            BaseDir::Config => Some(("XDG_CONFIG_DIRS", "/etc/xdg")),
+            BaseDir::Data => Some(("XDG_DATA_DIRS", "/usr/local/share:/usr/share")),
+            _ => None,
+        }
+    }
+}
+
+/// Returns where a file belonging to `base` should be written:
+/// `relative` joined onto the user's directory for `base`. Returns `None` if
+/// that directory can't be determined, such as when `HOME` isn't set.
+///
+/// The directory isn't created, and may not exist yet.
+pub fn place(base: BaseDir, relative: impl AsRef<Path>) -> Option<StackPathBuf> {
+    place_with(base, relative, |name| env::var_os(name))
+}
+
+/// Like [`place`], but looks variables up with `lookup` instead of reading
+/// the environment.
+pub fn place_with(
+    base: BaseDir,
+    relative: impl AsRef<Path>,
+    mut lookup: impl FnMut(&OsStr) -> Option<OsString>,
+) -> Option<StackPathBuf> {
+    let mut path = StackPathBuf::new();
+    if !push_home(&mut path, base, &mut lookup) {
+        return None;
+    }
+    path.push(relative);
+    Some(path)
+}
+
+/// Searches the user's directory for `base`, then each of the system
+/// directories for `base`, for `relative`, and returns the first path that
+/// exists.
+///
+/// Every candidate is built in the same buffer. Entries in `XDG_*_DIRS` that
+/// aren't absolute are skipped, as the specification requires.
+pub fn find(base: BaseDir, relative: impl AsRef<Path>) -> Option<StackPathBuf> {
+    find_with(base, relative, |name| env::var_os(name))
+}
+
+/// Like [`find`], but looks variables up with `lookup` instead of reading
+/// the environment.
+pub fn find_with(
+    base: BaseDir,
+    relative: impl AsRef<Path>,
+    mut lookup: impl FnMut(&OsStr) -> Option<OsString>,
+) -> Option<StackPathBuf> {
+    let relative = relative.as_ref();
+    let mut path = StackPathBuf::new();
+    if push_home(&mut path, base, &mut lookup) {
+        path.push(relative);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+
+    let (var, default) = base.dirs_var()?;
+    let dirs = lookup(OsStr::new(var)).filter(|dirs| !dirs.is_empty());
+    let dirs = dirs.as_deref().unwrap_or(OsStr::new(default));
+    for dir in dirs.as_bytes().split(|&b| b == b':') {
+        if dir.first() != Some(&b'/') {
+            continue;
+        }
+        path.clear();
+        path.push(OsStr::from_bytes(dir));
+        path.push(relative);
+        if path.exists() {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// Writes the user's directory for `base` into `path`. Returns false if it
+/// can't be determined.
+fn push_home(
+    path: &mut StackPathBuf,
+    base: BaseDir,
+    lookup: &mut impl FnMut(&OsStr) -> Option<OsString>,
+) -> bool {
+    // Relative values are invalid, and have to be ignored
+    if let Some(dir) = lookup(OsStr::new(base.home_var())) {
+        if Path::new(&dir).is_absolute() {
+            path.push(dir);
+            return true;
+        }
+    }
+    match (base.home_default(), lookup(OsStr::new("HOME"))) {
+        (Some(default), Some(home)) if !home.is_empty() => {
+            path.push(home);
+            path.push(default);
+            true
+        }
+        _ => false,
+    }
+}